@@ -5,12 +5,23 @@ const LWC_VERSION: &str = "5.2.0";
 const LWC_SHA256: &str = "66ac22df1b08de08ec2fae2b401b0f9731a4653a28e18a9837c7c3553c33dbe2";
 
 fn main() {
+	cfg_aliases();
 	git_version();
 	log_directives();
 	lightweight_charts();
 	deprecate();
 }
 
+/// `cfg(native)`: everything but the browser build (`wasm32` with the `wasm` feature), which logs to the devtools console
+/// instead of to files and stderr.
+fn cfg_aliases() {
+	println!("cargo:rustc-check-cfg=cfg(native)");
+	let browser = std::env::var("CARGO_CFG_TARGET_ARCH").is_ok_and(|arch| arch == "wasm32") && std::env::var_os("CARGO_FEATURE_WASM").is_some();
+	if !browser {
+		println!("cargo:rustc-cfg=native");
+	}
+}
+
 fn git_version() {
 	println!("cargo:rerun-if-changed=.git/HEAD");
 	println!("cargo:rerun-if-changed=.git/refs/");
@@ -202,10 +213,11 @@ fn collect_force_updates(dir: &std::path::Path, target_version: &str, updates: &
 			for (line_num, line) in content.lines().enumerate() {
 				let trimmed = line.trim_start();
 				if trimmed.starts_with("#[deprecated") {
-					// Check if since is missing or differs from target
+					// Without `since`, the target version is the deadline already; only differing ones are rewritten
 					match extract_since(trimmed) {
+						None => {}
 						Some(v) if v == target_version => {} // Already correct
-						_ => updates.push((path.display().to_string(), line_num, line.to_string())),
+						Some(_) => updates.push((path.display().to_string(), line_num, line.to_string())),
 					}
 				}
 			}
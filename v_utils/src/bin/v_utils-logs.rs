@@ -0,0 +1,68 @@
+//! Viewer for the logs `v_utils::utils::init_subscriber` writes.
+//!
+//! ```sh
+//! v_utils-logs my_app -f --filter 'info,my_app::orders=debug'
+//! v_utils-logs --file /var/log/bot/orders.log -n 200
+//! ```
+
+use std::{io::IsTerminal as _, path::PathBuf};
+
+use eyre::{Result, bail, eyre};
+use v_utils::io::logs::{self, LogFilter, TailOptions};
+
+const USAGE: &str = "\
+Usage: v_utils-logs <APP> [--fname NAME] [OPTIONS]
+       v_utils-logs --file <PATH> [OPTIONS]
+
+Locates the live log of <APP> the way `LogDestination::xdg` places it ($XDG_STATE_HOME/<APP>/<NAME>.log, or
+<NAME>.current.log when rotated).
+
+Options:
+  -f, --follow              Keep printing new lines, across log rotations
+  -n, --lines <N>           Start <N> records before the end
+      --filter <DIRECTIVES> Show only what these `_log_directives`-style directives enable
+      --color <WHEN>        auto | always | never [default: auto]
+  -h, --help                Print this help";
+
+fn main() -> Result<()> {
+	let mut app = None;
+	let mut fname = None;
+	let mut file = None;
+	let mut opts = TailOptions::default();
+	let mut color = None;
+
+	let mut args = std::env::args().skip(1);
+	while let Some(arg) = args.next() {
+		let mut value = |flag: &str| args.next().ok_or_else(|| eyre!("`{flag}` expects a value\n\n{USAGE}"));
+		match arg.as_str() {
+			"-h" | "--help" => {
+				println!("{USAGE}");
+				return Ok(());
+			}
+			"-f" | "--follow" => opts.follow = true,
+			"-n" | "--lines" => opts.lines = Some(value(&arg)?.parse()?),
+			"--filter" => opts.filter = value(&arg)?.parse::<LogFilter>()?,
+			"--fname" => fname = Some(value(&arg)?),
+			"--file" => file = Some(PathBuf::from(value(&arg)?)),
+			"--color" => color = Some(value(&arg)?),
+			flag if flag.starts_with('-') => bail!("unknown option `{flag}`\n\n{USAGE}"),
+			_ if app.is_none() => app = Some(arg),
+			_ => bail!("unexpected argument `{arg}`\n\n{USAGE}"),
+		}
+	}
+
+	opts.color = match color.as_deref() {
+		None | Some("auto") => std::io::stdout().is_terminal(),
+		Some("always") => true,
+		Some("never") => false,
+		Some(other) => bail!("`--color` expects auto, always or never, got `{other}`"),
+	};
+	let path = match (file, app) {
+		(Some(path), None) => path,
+		(None, Some(app)) => logs::locate(&app, fname.as_deref())?,
+		_ => bail!("expected exactly one of <APP> or `--file`\n\n{USAGE}"),
+	};
+
+	logs::tail(&path, &opts, &mut std::io::stdout().lock())?;
+	Ok(())
+}
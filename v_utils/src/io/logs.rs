@@ -0,0 +1,612 @@
+//! Reading back the logs [`init_subscriber`](crate::utils::init_subscriber) writes: tail, follow across rotations,
+//! filter with `_log_directives` syntax, render for humans. The `v_utils-logs` binary is a thin CLI over this.
+//!
+//! The log is read as a stream of concatenated JSON values, so both [`LogFormat::JsonLines`](crate::utils::tracing::LogFormat::JsonLines)
+//! and [`LogFormat::IndentedJson`](crate::utils::tracing::LogFormat::IndentedJson) are filtered and rendered. Lines that aren't
+//! JSON (e.g. from the default [`LogFormat::PrettyJson`](crate::utils::tracing::LogFormat::PrettyJson)) are passed through as they are.
+
+use std::{
+	fs::File,
+	io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write},
+	path::Path,
+	str::FromStr,
+	thread,
+	time::Duration,
+};
+
+use eyre::{Result, bail, eyre};
+use serde_json::{Map, Value};
+use tracing::{Level, level_filters::LevelFilter};
+
+/// How often a followed log is checked for new lines, or for having been rotated
+const FOLLOW_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// One event, as written by [`LogFormat::JsonLines`](crate::utils::tracing::LogFormat::JsonLines) or [`LogFormat::IndentedJson`](crate::utils::tracing::LogFormat::IndentedJson).
+#[derive(Clone, Debug, PartialEq)]
+pub struct LogRecord {
+	pub timestamp: Option<String>,
+	pub level: Level,
+	pub target: String,
+	pub message: String,
+	/// Event fields, without `message`.
+	pub fields: Map<String, Value>,
+	/// Outermost first.
+	pub spans: Vec<LogSpan>,
+	pub filename: Option<String>,
+	pub line_number: Option<u64>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LogSpan {
+	pub name: String,
+	pub fields: Map<String, Value>,
+}
+
+impl LogRecord {
+	/// `None` if `line` isn't a JSON event.
+	pub fn parse(line: &str) -> Option<Self> {
+		Self::from_json(serde_json::from_str(line).ok()?)
+	}
+
+	/// `None` if `event` isn't an object with at least a `level`.
+	pub fn from_json(event: Value) -> Option<Self> {
+		let Value::Object(mut event) = event else { return None };
+		let level = event.get("level")?.as_str()?.parse().ok()?;
+		let mut fields = match event.remove("fields") {
+			Some(Value::Object(fields)) => fields,
+			_ => Map::new(),
+		};
+		let message = match fields.remove("message") {
+			Some(Value::String(s)) => s,
+			Some(other) => other.to_string(),
+			None => String::new(),
+		};
+		let spans = match event.remove("spans") {
+			Some(Value::Array(spans)) => spans.into_iter().filter_map(LogSpan::from_json).collect(),
+			_ => event.remove("span").and_then(LogSpan::from_json).into_iter().collect(),
+		};
+		Some(Self {
+			timestamp: event.get("timestamp").and_then(Value::as_str).map(str::to_owned),
+			level,
+			target: event.get("target").and_then(Value::as_str).unwrap_or_default().to_owned(),
+			message,
+			fields,
+			spans,
+			filename: event.get("filename").and_then(Value::as_str).map(str::to_owned),
+			line_number: event.get("line_number").and_then(Value::as_u64),
+		})
+	}
+
+	/// One line, laid out like [`LogFormat::Text`](crate::utils::tracing::LogFormat::Text): `{timestamp} {LEVEL} {spans}: {target}: {message} {fields}`.
+	pub fn render(&self, color: bool) -> String {
+		let paint = |code: &str, s: &str| if color { format!("\x1b[{code}m{s}\x1b[0m") } else { s.to_owned() };
+		let level_code = match self.level {
+			Level::ERROR => "31",
+			Level::WARN => "33",
+			Level::INFO => "32",
+			Level::DEBUG => "34",
+			Level::TRACE => "35",
+		};
+
+		let mut out = String::new();
+		if let Some(ts) = &self.timestamp {
+			out.push_str(&paint("2", ts));
+			out.push(' ');
+		}
+		out.push_str(&paint(level_code, &format!("{:>5}", self.level.as_str())));
+		out.push(' ');
+		for span in &self.spans {
+			out.push_str(&paint("1", &span.name));
+			if !span.fields.is_empty() {
+				out.push_str(&format!("{{{}}}", join_fields(&span.fields, " ")));
+			}
+			out.push(':');
+		}
+		if !self.spans.is_empty() {
+			out.push(' ');
+		}
+		out.push_str(&paint("2", &format!("{}:", self.target)));
+		out.push(' ');
+		out.push_str(&self.message);
+		if !self.fields.is_empty() {
+			out.push(' ');
+			out.push_str(&paint("3", &join_fields(&self.fields, " ")));
+		}
+		out
+	}
+}
+
+impl LogSpan {
+	fn from_json(span: Value) -> Option<Self> {
+		let Value::Object(mut fields) = span else { return None };
+		let name = fields.remove("name")?.as_str()?.to_owned();
+		Some(Self { name, fields })
+	}
+}
+
+fn value_str(v: &Value) -> String {
+	match v {
+		Value::String(s) => s.clone(),
+		other => other.to_string(),
+	}
+}
+
+fn join_fields(fields: &Map<String, Value>, sep: &str) -> String {
+	fields.iter().map(|(k, v)| format!("{k}={}", value_str(v))).collect::<Vec<_>>().join(sep)
+}
+
+/// Which records to show, in the directive syntax of `_log_directives` / `RUST_LOG`:
+/// `warn,my_crate::orders=debug,my_crate[fill{venue=binance}]=trace`.
+///
+/// Like `EnvFilter`, the most specific directive matching a record decides, and a record no directive matches is
+/// hidden. Unlike it, `{field=value}` also matches the event's own fields, not only those of its spans.
+#[derive(Clone, Debug)]
+pub struct LogFilter {
+	directives: Vec<Directive>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Directive {
+	target: Option<String>,
+	span: Option<String>,
+	/// `(name, Some(value))` for `name=value`, `(name, None)` for a bare `name`.
+	fields: Vec<(String, Option<String>)>,
+	level: LevelFilter,
+}
+
+impl LogFilter {
+	pub fn matches(&self, record: &LogRecord) -> bool {
+		self.directives
+			.iter()
+			.filter(|d| d.applies_to(record))
+			.max_by_key(|d| d.specificity())
+			.is_some_and(|d| record.level <= d.level)
+	}
+}
+
+impl Default for LogFilter {
+	/// Everything
+	fn default() -> Self {
+		Self {
+			directives: vec![Directive {
+				target: None,
+				span: None,
+				fields: Vec::new(),
+				level: LevelFilter::TRACE,
+			}],
+		}
+	}
+}
+
+impl FromStr for LogFilter {
+	type Err = eyre::Report;
+
+	fn from_str(s: &str) -> Result<Self> {
+		let normalized = crate::utils::tracing::normalize_directives(s);
+		let directives = split_top_level(&normalized, ',')
+			.into_iter()
+			.filter(|d| !d.trim().is_empty())
+			.map(|d| Directive::parse(d.trim()))
+			.collect::<Result<_>>()?;
+		Ok(Self { directives })
+	}
+}
+
+impl Directive {
+	fn parse(s: &str) -> Result<Self> {
+		let (spec, level) = match split_top_level(s, '=').as_slice() {
+			[spec] => match spec.parse::<LevelFilter>() {
+				Ok(level) => ("", level),
+				// A bare target enables everything under it.
+				Err(_) => (*spec, LevelFilter::TRACE),
+			},
+			[spec, level] => (*spec, level.parse::<LevelFilter>().map_err(|_| eyre!("invalid level `{level}` in log directive `{s}`"))?),
+			_ => bail!("invalid log directive `{s}`: more than one `=` outside of `[...]`"),
+		};
+
+		let (target, selector) = match spec.split_once('[') {
+			Some((target, rest)) => (target, Some(rest.strip_suffix(']').ok_or_else(|| eyre!("unclosed `[` in log directive `{s}`"))?)),
+			None => (spec, None),
+		};
+		let (span, fields) = match selector {
+			None => (None, Vec::new()),
+			Some(selector) => {
+				let (name, fields) = match selector.split_once('{') {
+					Some((name, rest)) => (name, rest.strip_suffix('}').ok_or_else(|| eyre!("unclosed `{{` in log directive `{s}`"))?),
+					None => (selector, ""),
+				};
+				let fields = fields
+					.split(',')
+					.map(str::trim)
+					.filter(|f| !f.is_empty())
+					.map(|f| match f.split_once('=') {
+						Some((k, v)) => (k.trim().to_owned(), Some(v.trim().trim_matches('"').to_owned())),
+						None => (f.to_owned(), None),
+					})
+					.collect();
+				((!name.is_empty()).then(|| name.to_owned()), fields)
+			}
+		};
+
+		Ok(Self {
+			target: (!target.is_empty()).then(|| target.to_owned()),
+			span,
+			fields,
+			level,
+		})
+	}
+
+	fn applies_to(&self, record: &LogRecord) -> bool {
+		if let Some(target) = &self.target
+			&& !record.target.starts_with(target.as_str())
+		{
+			return false;
+		}
+		if self.span.is_none() && self.fields.is_empty() {
+			return true;
+		}
+		let field_matches = |fields: &Map<String, Value>| {
+			self.fields.iter().all(|(name, value)| match (fields.get(name), value) {
+				(Some(actual), Some(expected)) => value_str(actual) == *expected,
+				(Some(_), None) => true,
+				(None, _) => false,
+			})
+		};
+		let in_span = record
+			.spans
+			.iter()
+			.any(|span| self.span.as_ref().is_none_or(|name| *name == span.name) && field_matches(&span.fields));
+		in_span || (self.span.is_none() && field_matches(&record.fields))
+	}
+
+	fn specificity(&self) -> (bool, usize, usize) {
+		(self.span.is_some() || !self.fields.is_empty(), self.fields.len(), self.target.as_ref().map_or(0, String::len))
+	}
+}
+
+/// Splits on `sep`, except inside `[...]` and `{...}`.
+fn split_top_level(s: &str, sep: char) -> Vec<&str> {
+	let mut parts = Vec::new();
+	let (mut depth, mut start) = (0usize, 0);
+	for (i, c) in s.char_indices() {
+		match c {
+			'[' | '{' => depth += 1,
+			']' | '}' => depth = depth.saturating_sub(1),
+			c if c == sep && depth == 0 => {
+				parts.push(&s[start..i]);
+				start = i + c.len_utf8();
+			}
+			_ => {}
+		}
+	}
+	parts.push(&s[start..]);
+	parts
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct TailOptions {
+	/// Start this many records before the end, instead of at the beginning. A record is a JSON value, or a line of
+	/// anything else.
+	pub lines: Option<usize>,
+	/// Keep waiting for new lines, following the log across rotations.
+	pub follow: bool,
+	pub filter: LogFilter,
+	pub color: bool,
+}
+
+/// Prints the log at `path` to `out`, rendered and filtered per `opts`. With [`TailOptions::follow`], only returns on error.
+///
+/// When `path` is the [`current_log_path`](crate::utils::current_log_path) a rotating [`LogDestination`](crate::utils::LogDestination) maintains, a rotation is
+/// noticed by the symlink resolving elsewhere; the old segment is drained before switching over.
+pub fn tail(path: &Path, opts: &TailOptions, out: &mut impl Write) -> io::Result<()> {
+	let mut file = File::open(path)?;
+	let mut resolved = std::fs::canonicalize(path).ok();
+	if let Some(n) = opts.lines {
+		let start = offset_of_last_records(&mut file, n)?;
+		file.seek(SeekFrom::Start(start))?;
+	}
+	let mut reader = BufReader::new(file);
+	let mut values = ValueStream::default();
+
+	let mut line = String::new();
+	loop {
+		if reader.read_line(&mut line)? > 0 {
+			// A partial line is only complete once the writer gets to its newline.
+			if line.ends_with('\n') || !opts.follow {
+				for chunk in values.feed(line.trim_end_matches(['\n', '\r'])) {
+					emit(chunk, opts, out)?;
+				}
+				line.clear();
+			}
+			continue;
+		}
+		if !opts.follow {
+			return values.finish().map_or(Ok(()), |chunk| emit(chunk, opts, out));
+		}
+		out.flush()?;
+
+		let now_resolved = std::fs::canonicalize(path).ok();
+		if now_resolved.is_some() && now_resolved != resolved {
+			if !line.is_empty() {
+				for chunk in values.feed(line.trim_end_matches(['\n', '\r'])) {
+					emit(chunk, opts, out)?;
+				}
+				line.clear();
+			}
+			if let Some(chunk) = values.finish() {
+				emit(chunk, opts, out)?;
+			}
+			reader = BufReader::new(File::open(path)?);
+			resolved = now_resolved;
+			continue;
+		}
+		thread::sleep(FOLLOW_POLL_INTERVAL);
+	}
+}
+
+/// What [`ValueStream`] makes of the lines fed to it.
+#[derive(Debug, PartialEq)]
+enum Chunk {
+	Record(LogRecord),
+	/// Not an event: a line of some other format, or JSON that isn't one
+	Other(String),
+}
+
+/// Lines of a log, put back together into the JSON values [`LogFormat::IndentedJson`](crate::utils::tracing::LogFormat::IndentedJson)
+/// spreads over several of them.
+#[derive(Debug, Default)]
+struct ValueStream {
+	/// Lines of a value that isn't complete yet
+	pending: String,
+	/// Brackets opened and not yet closed in `pending`, outside of strings
+	depth: usize,
+	in_string: bool,
+	escaped: bool,
+}
+
+impl ValueStream {
+	/// Takes one whole line, returning whatever it completes.
+	fn feed(&mut self, line: &str) -> Vec<Chunk> {
+		let mut chunks = Vec::new();
+		// Values are indented past their first line, so an unindented one means the pending value was cut short.
+		if !self.pending.is_empty() && !line.starts_with([' ', '\t', '}', ']']) {
+			chunks.extend(self.finish());
+		}
+		if self.pending.is_empty() && !line.trim_start().starts_with('{') {
+			chunks.push(Chunk::Other(line.to_owned()));
+			return chunks;
+		}
+		let first_line = self.pending.is_empty();
+		self.pending.push_str(line);
+		self.pending.push('\n');
+		self.scan(line);
+		// Only a value's first line (to catch garbage early) and the one closing it are worth parsing.
+		if first_line || self.depth == 0 {
+			self.parse_pending(&mut chunks);
+		}
+		chunks
+	}
+
+	/// Tracks bracket depth through `s`, the text just appended to `pending`.
+	fn scan(&mut self, s: &str) {
+		for c in s.chars() {
+			match (self.in_string, c) {
+				(true, _) if self.escaped => self.escaped = false,
+				(true, '\\') => self.escaped = true,
+				(true, '"') => self.in_string = false,
+				(true, _) => {}
+				(false, '"') => self.in_string = true,
+				(false, '{' | '[') => self.depth += 1,
+				(false, '}' | ']') => self.depth = self.depth.saturating_sub(1),
+				(false, _) => {}
+			}
+		}
+	}
+
+	fn parse_pending(&mut self, chunks: &mut Vec<Chunk>) {
+		let mut stream = serde_json::Deserializer::from_str(&self.pending).into_iter::<Value>();
+		loop {
+			let start = stream.byte_offset();
+			match stream.next() {
+				None => {
+					self.reset();
+					return;
+				}
+				Some(Ok(value)) => chunks.push(match LogRecord::from_json(value) {
+					Some(record) => Chunk::Record(record),
+					None => Chunk::Other(self.pending[start..stream.byte_offset()].trim().to_owned()),
+				}),
+				Some(Err(e)) if e.is_eof() => {
+					let rest = self.pending.split_off(start);
+					self.reset();
+					self.scan(&rest);
+					self.pending = rest;
+					return;
+				}
+				Some(Err(_)) => {
+					chunks.push(Chunk::Other(self.pending[start..].trim().to_owned()));
+					self.reset();
+					return;
+				}
+			}
+		}
+	}
+
+	fn reset(&mut self) {
+		*self = Self::default();
+	}
+
+	/// What's left of a value the log ends in the middle of.
+	fn finish(&mut self) -> Option<Chunk> {
+		let rest = std::mem::take(&mut self.pending);
+		self.reset();
+		(!rest.trim().is_empty()).then(|| Chunk::Other(rest.trim_end().to_owned()))
+	}
+}
+
+fn emit(chunk: Chunk, opts: &TailOptions, out: &mut impl Write) -> io::Result<()> {
+	match chunk {
+		Chunk::Record(record) if opts.filter.matches(&record) => writeln!(out, "{}", record.render(opts.color)),
+		Chunk::Record(_) => Ok(()),
+		Chunk::Other(line) if line.trim().is_empty() => Ok(()),
+		Chunk::Other(line) => writeln!(out, "{line}"),
+	}
+}
+
+/// Byte offset at which the last `n` records of `file` start, a record being one of the JSON values [`ValueStream`]
+/// reads or a line of anything else.
+///
+/// Scans back line by line: an [`IndentedJson`](crate::utils::tracing::LogFormat::IndentedJson) value spans from a `{` at the
+/// start of a line to the `}` at the start of a later one, with everything in between indented.
+fn offset_of_last_records(file: &mut File, n: usize) -> io::Result<u64> {
+	let len = file.seek(SeekFrom::End(0))?;
+	if n == 0 {
+		return Ok(len);
+	}
+	let (mut seen, mut in_value) = (0, false);
+	// Whether the line starting with `first` (`None` past the end of the file) completes the `n`th record from the end.
+	let mut is_nth = |first: Option<u8>| {
+		match (in_value, first) {
+			(_, None | Some(b'\n' | b'\r')) => return false,
+			(false, Some(b'}')) => in_value = true,
+			(true, Some(b'{')) => {
+				in_value = false;
+				seen += 1;
+			}
+			(true, _) => {}
+			(false, _) => seen += 1,
+		}
+		seen == n
+	};
+
+	let mut buf = [0u8; 8192];
+	let mut pos = len;
+	// The byte after the one being looked at, i.e. the first of the line a newline ends in
+	let mut next = None;
+	while pos > 0 {
+		let chunk = pos.min(buf.len() as u64) as usize;
+		pos -= chunk as u64;
+		file.seek(SeekFrom::Start(pos))?;
+		file.read_exact(&mut buf[..chunk])?;
+		for i in (0..chunk).rev() {
+			if buf[i] == b'\n' && is_nth(next) {
+				return Ok(pos + i as u64 + 1);
+			}
+			next = Some(buf[i]);
+		}
+	}
+	Ok(0)
+}
+
+/// Live log of app `dname` under the same XDG rules as [`LogDestination::xdg`](crate::utils::LogDestination::xdg): its
+/// [`current_log_path`](crate::utils::current_log_path) if it's rotated, or else the configured path.
+#[cfg(feature = "xdg")]
+pub fn locate(dname: &str, fname: Option<&str>) -> Result<std::path::PathBuf> {
+	let configured = crate::utils::xdg_log_path(dname, fname).ok_or_else(|| eyre!("No XDG state home to look for `{dname}` logs in (is $HOME set?)"))?;
+	let current = crate::utils::current_log_path(&configured);
+	match [current, configured.clone()].into_iter().find(|path| std::fs::symlink_metadata(path).is_ok()) {
+		Some(path) => Ok(path),
+		None => bail!("No log for `{dname}` at {}", configured.display()),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const EVENT: &str = r#"{"timestamp":"2026-10-18T12:00:00.000000Z","level":"DEBUG","fields":{"message":"filled","qty":3},"target":"bot::orders","span":{"id":7,"name":"fill"},"spans":[{"name":"session"},{"id":7,"name":"fill"}]}"#;
+
+	fn shown(filter: &str) -> bool {
+		filter.parse::<LogFilter>().unwrap().matches(&LogRecord::parse(EVENT).unwrap())
+	}
+
+	#[test]
+	fn parse_and_render() {
+		let record = LogRecord::parse(EVENT).unwrap();
+		assert_eq!(record.level, Level::DEBUG);
+		assert_eq!(record.message, "filled");
+		assert_eq!(record.spans.len(), 2);
+		assert_eq!(record.render(false), "2026-10-18T12:00:00.000000Z DEBUG session:fill{id=7}: bot::orders: filled qty=3");
+		assert_eq!(LogRecord::parse("  2026-10-18T12:00:00Z  INFO bot: not json"), None);
+	}
+
+	#[test]
+	fn filter_follows_directive_precedence() {
+		assert!(shown("debug"));
+		assert!(!shown("info"));
+		assert!(shown("info,bot::orders=debug"));
+		assert!(!shown("debug,bot=info"));
+		assert!(shown("info,bot[fill]=debug"));
+		assert!(!shown("info,bot[fill{id=8}]=debug"));
+		assert!(shown("info,[{qty=3}]=debug"));
+		assert!(!shown("other=trace"));
+		assert!(shown("# comment\nwarn\nbot::orders"));
+		assert!("bot=loud".parse::<LogFilter>().is_err());
+	}
+
+	#[test]
+	fn indented_json_is_filtered() {
+		use tracing_subscriber::layer::SubscriberExt as _;
+
+		let tmp = tempfile::tempdir().unwrap();
+		let path = tmp.path().join("app.log");
+		let file = File::create(&path).unwrap();
+		let layer = crate::utils::tracing::format::fmt_layer(crate::utils::tracing::LogFormat::IndentedJson, Default::default(), std::sync::Mutex::new(file), false, true);
+		tracing::subscriber::with_default(tracing_subscriber::registry().with(layer), || {
+			let _span = tracing::info_span!("fill", venue = "binance").entered();
+			tracing::debug!(target: "bot::orders", qty = 3, "filled");
+			tracing::info!(target: "bot::orders", qty = 5, "filled");
+			tracing::warn!(target: "bot::feed", "stale");
+		});
+		let written = std::fs::read_to_string(&path).unwrap();
+		assert!(written.lines().count() > 3, "not multi-line:\n{written}");
+
+		let opts = TailOptions {
+			filter: "warn,bot::orders[fill{venue=binance}]=info".parse().unwrap(),
+			..Default::default()
+		};
+		let mut out = Vec::new();
+		tail(&path, &opts, &mut out).unwrap();
+		let out = String::from_utf8(out).unwrap();
+		let shown: Vec<_> = out.lines().map(|l| l.split_once(' ').unwrap().1).collect();
+		assert_eq!(shown, [" INFO fill{venue=binance}: bot::orders: filled qty=5", " WARN fill{venue=binance}: bot::feed: stale"]);
+
+		let last_two = TailOptions { lines: Some(2), ..Default::default() };
+		let mut out = Vec::new();
+		tail(&path, &last_two, &mut out).unwrap();
+		let out = String::from_utf8(out).unwrap();
+		let shown: Vec<_> = out.lines().map(|l| l.split_once(' ').unwrap().1).collect();
+		assert_eq!(shown, [" INFO fill{venue=binance}: bot::orders: filled qty=5", " WARN fill{venue=binance}: bot::feed: stale"]);
+	}
+
+	#[test]
+	fn value_stream_passes_other_lines_through() {
+		let mut values = ValueStream::default();
+		assert_eq!(values.feed("plain text"), [Chunk::Other("plain text".to_owned())]);
+		assert_eq!(values.feed("{"), []);
+		assert_eq!(values.feed(r#"  "level": "INFO""#), []);
+		assert!(matches!(values.feed("}").as_slice(), [Chunk::Record(r)] if r.level == Level::INFO));
+		assert_eq!(values.feed("{oops"), [Chunk::Other("{oops".to_owned())]);
+		assert_eq!(values.feed(r#"{"level": "#), []);
+		assert_eq!(values.finish(), Some(Chunk::Other(r#"{"level":"#.to_owned())));
+		// A value cut short (say, by a crash) doesn't swallow what comes after it.
+		assert_eq!(values.feed(r#"{"msg": "{ not a bracket","#), []);
+		assert_eq!(values.feed("after restart"), [Chunk::Other(r#"{"msg": "{ not a bracket","#.to_owned()), Chunk::Other("after restart".to_owned())]);
+	}
+
+	#[test]
+	fn offset_of_last_records_counts_from_the_end() {
+		let tmp = tempfile::tempdir().unwrap();
+		let path = tmp.path().join("x.log");
+		let log = "a\n{\n  \"level\": \"INFO\",\n  \"fields\": {}\n}\n{\"level\":\"WARN\"}\nb\n\n";
+		std::fs::write(&path, log).unwrap();
+		let mut file = File::open(&path).unwrap();
+		let at = |s: &str| log.find(s).unwrap() as u64;
+		assert_eq!(offset_of_last_records(&mut file, 1).unwrap(), at("b\n"));
+		assert_eq!(offset_of_last_records(&mut file, 2).unwrap(), at("{\"level"));
+		assert_eq!(offset_of_last_records(&mut file, 3).unwrap(), at("{\n"));
+		assert_eq!(offset_of_last_records(&mut file, 4).unwrap(), 0);
+		assert_eq!(offset_of_last_records(&mut file, 9).unwrap(), 0);
+		assert_eq!(offset_of_last_records(&mut file, 0).unwrap(), log.len() as u64);
+	}
+}
@@ -4,6 +4,10 @@ pub use cli::*;
 pub mod expanded_path;
 pub use expanded_path::*;
 
+/// Not glob-exported: `tail`/`locate` read too generic at the crate root.
+#[cfg(all(feature = "tracing", not(target_arch = "wasm32")))]
+pub mod logs;
+
 #[cfg(feature = "async-io")]
 pub mod file_open;
 #[cfg(feature = "async-io")]
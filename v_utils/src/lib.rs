@@ -48,6 +48,8 @@ pub mod __internal {
 
 	#[cfg(feature = "cli")]
 	pub extern crate config;
+	#[cfg(feature = "miette")]
+	pub extern crate miette;
 	#[cfg(feature = "cli")]
 	pub extern crate facet;
 	#[cfg(feature = "cli")]
@@ -284,4 +286,50 @@ pub mod macros {
 		type Flags;
 		fn collect_config(flags: &Self::Flags, map: &mut crate::__internal::config::Map<String, crate::__internal::config::Value>);
 	}
+
+	#[cfg(feature = "cli")]
+	pub use crate::__internal::config::FileFormat;
+
+	/// What `#[derive(Settings)]`'s `try_build` does when the config lacks a field that has a default.
+	///
+	/// Chosen, highest precedence first, by an `--extend-policy` flag passed to `try_build_with_policy`,
+	/// the `<CARGO_PKG_NAME>_EXTEND_POLICY` env var, and `#[settings(extend_policy = "..")]` on the
+	/// struct; `Prompt` if none is set. Parses from and displays as kebab-case (`never-extend`).
+	#[cfg(feature = "cli")]
+	#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, strum::Display, strum::EnumString)]
+	#[strum(serialize_all = "kebab-case")]
+	pub enum ExtendPolicy {
+		/// Ask whether to write the default into the config file (`--yes` answers for you).
+		/// When stdin is not a terminal there is nobody to ask, and this acts as `NeverExtend`.
+		#[default]
+		Prompt,
+		/// Write the default into the config file without asking.
+		AlwaysExtend,
+		/// Fail with the missing-field error.
+		NeverExtend,
+		/// Use the default for this run only, leaving the config file untouched.
+		ExtendInMemoryOnly,
+	}
+
+	/// In-memory stand-ins for everything `try_build` reads from the process: the config file,
+	/// the environment, and the CLI flags. Consumed by the `try_build_from` that
+	/// `#[derive(Settings)]` generates.
+	///
+	/// ```ignore
+	/// let cfg = AppConfig::try_build_from(SettingsSources {
+	///     file: Some((FileFormat::Toml, "port = 8080".to_owned())),
+	///     env: HashMap::from([("MY_APP__HOST".to_owned(), "example.com".to_owned())]),
+	///     flags: SettingsFlags::default(),
+	/// })?;
+	/// ```
+	#[cfg(feature = "cli")]
+	#[derive(Clone, Debug, Default)]
+	pub struct SettingsSources<F> {
+		/// Contents of the config file and the format to parse them as. `None` behaves like no config file on disk.
+		pub file: Option<(FileFormat, String)>,
+		/// Environment variables by their full name, i.e. `<CARGO_PKG_NAME>__<FIELD>`, exactly as `try_build` would read them.
+		pub env: std::collections::HashMap<String, String>,
+		/// The generated `SettingsFlags`. Its `config` path is ignored — the file comes from `file`.
+		pub flags: F,
+	}
 }
@@ -0,0 +1,431 @@
+//! What the code generated by `CompactFormatNamed`, `CompactFormatMap`, `TryParseVariants` and `OptionalFieldsFromVecStr`
+//! calls into: splitting on separators outside of nesting, quoting values that would otherwise be split, `[a,b,c]` lists,
+//! the errors, and the grammar.
+//!
+//! `{..}` and `[..]` nest, `"..."` quotes, with `\"` and `\\` escaped inside.
+
+use std::{any::Any, borrow::Cow, fmt, str::FromStr};
+
+/// A compact string that didn't parse: where, and what was expected there.
+///
+/// With the `miette` feature, also a `miette::Diagnostic`, pointing at the offending part of the input.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CompactParseError {
+	/// The whole string being parsed, nested values included
+	pub input: String,
+	/// Byte offset into `input` of the part that's off
+	pub offset: usize,
+	/// Byte length of that part; `0` when something is missing at `offset`
+	pub len: usize,
+	/// What should have been there, e.g. ``a field keyed by any of ["tp", "n"]``
+	pub expected: String,
+	/// Why what's there didn't do, e.g. the `FromStr` error of the field's type
+	pub reason: Option<String>,
+}
+
+impl CompactParseError {
+	pub fn new(input: &str, offset: usize, len: usize, expected: impl Into<String>) -> Self {
+		Self {
+			input: input.to_owned(),
+			offset,
+			len,
+			expected: expected.into(),
+			reason: None,
+		}
+	}
+
+	/// For something that should have been at the end of `input`.
+	pub fn missing(input: &str, expected: impl Into<String>) -> Self {
+		Self::new(input, input.len(), 0, expected)
+	}
+
+	/// For `part` of `input` not being what's `expected`.
+	pub fn at(input: &str, part: &str, expected: impl Into<String>) -> Self {
+		Self::new(input, offset(input, part), part.len(), expected)
+	}
+
+	pub fn reason(mut self, reason: impl fmt::Display) -> Self {
+		self.reason = Some(reason.to_string());
+		self
+	}
+
+	/// The part of the input that's off.
+	pub fn found(&self) -> &str {
+		self.input.get(self.offset..self.offset + self.len).unwrap_or_default()
+	}
+
+	/// This error, of a value that sits at `at` in `input`, as one of `input`'s.
+	fn within(self, input: &str, at: usize) -> Self {
+		Self {
+			input: input.to_owned(),
+			offset: at + self.offset,
+			..self
+		}
+	}
+}
+
+impl fmt::Display for CompactParseError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "expected {} at byte {} of `{}`", self.expected, self.offset, self.input)?;
+		match (self.found(), self.offset == self.input.len()) {
+			("", true) => write!(f, ", found the end")?,
+			("", false) => {}
+			(found, _) => write!(f, ", found `{found}`")?,
+		}
+		if let Some(reason) = &self.reason {
+			write!(f, ": {reason}")?;
+		}
+		Ok(())
+	}
+}
+
+impl std::error::Error for CompactParseError {}
+
+#[cfg(feature = "miette")]
+impl miette::Diagnostic for CompactParseError {
+	fn source_code(&self) -> Option<&dyn miette::SourceCode> {
+		Some(&self.input)
+	}
+
+	fn labels(&self) -> Option<Box<dyn Iterator<Item = miette::LabeledSpan> + '_>> {
+		let label = miette::LabeledSpan::new_primary_with_span(Some(format!("expected {}", self.expected)), (self.offset, self.len));
+		Some(Box::new(std::iter::once(label)))
+	}
+
+	fn help<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+		self.reason.as_ref().map(|reason| Box::new(reason) as Box<dyn fmt::Display>)
+	}
+}
+
+/// What a `TryParseVariants` enum fails to parse with.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TryParseError {
+	/// No variant took `input`; why not, for each, in the order they were tried: prefixed ones (longest prefix first), then the rest in declaration order
+	NoMatch {
+		ty: &'static str,
+		input: String,
+		attempts: Vec<(&'static str, String)>,
+	},
+	/// `#[try_parse(strict)]`, and more than one variant took `input`
+	Ambiguous { ty: &'static str, input: String, variants: Vec<&'static str> },
+}
+
+impl fmt::Display for TryParseError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::NoMatch { ty, input, attempts } => {
+				write!(f, "`{input}` is none of {ty}'s variants")?;
+				for (variant, reason) in attempts {
+					write!(f, "\n  {variant}: {reason}")?;
+				}
+				Ok(())
+			}
+			Self::Ambiguous { ty, input, variants } => write!(f, "`{input}` is more than one of {ty}'s variants: {}", variants.join(", ")),
+		}
+	}
+}
+
+impl std::error::Error for TryParseError {}
+
+/// What an `OptionalFieldsFromVecStr` struct fails to be made with: every token that didn't fit.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FromVecStrError {
+	pub ty: &'static str,
+	pub issues: Vec<FromVecStrIssue>,
+}
+
+/// One way an `OptionalFieldsFromVecStr` token didn't fit.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum FromVecStrIssue {
+	/// Neither `key=value` with a field's name, nor parsing as any of the fields left
+	Unmatched(String),
+	/// Parses as fields left of different types, so which one it's for is a guess
+	Ambiguous { token: String, fields: Vec<&'static str> },
+	/// `key=value` whose value doesn't parse as the field's type
+	Invalid { token: String, field: &'static str, reason: String },
+	/// `#[from_vec_str(strict)]`, and a field given more than once
+	Duplicate { field: &'static str, tokens: Vec<String> },
+}
+
+impl fmt::Display for FromVecStrError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "couldn't make {} out of the tokens", self.ty)?;
+		for issue in &self.issues {
+			write!(f, "\n  {issue}")?;
+		}
+		Ok(())
+	}
+}
+
+impl fmt::Display for FromVecStrIssue {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Unmatched(token) => write!(f, "`{token}`: fits none of the fields left"),
+			Self::Ambiguous { token, fields } => write!(f, "`{token}`: could be any of {}; say which with `key=value`", fields.join(", ")),
+			Self::Invalid { token, field, reason } => write!(f, "`{token}`: not a {field}: {reason}"),
+			Self::Duplicate { field, tokens } => write!(f, "{field} given more than once: `{}`", tokens.join("`, `")),
+		}
+	}
+}
+
+impl std::error::Error for FromVecStrError {}
+
+/// Splits `s` on each `sep` that's outside of braces, brackets and quotes. A trailing empty piece is dropped.
+pub fn split(s: &str, sep: char) -> Vec<&str> {
+	let mut pieces = Vec::new();
+	let mut start = 0;
+	scan(s, |i, c, depth| {
+		if c == sep && depth == 0 {
+			pieces.push(&s[start..i]);
+			start = i + c.len_utf8();
+		}
+	});
+	if start < s.len() {
+		pieces.push(&s[start..]);
+	}
+	pieces
+}
+
+/// `value`, in quotes if it would otherwise not read back as one value where `sep` separates them: when it's empty,
+/// starts with a quote, has unbalanced braces/brackets/quotes, or a `sep` at the top level.
+pub fn quote(value: &str, sep: char) -> Cow<'_, str> {
+	let mut needs_quotes = value.is_empty() || value.starts_with('"');
+	let (depth, in_quotes) = scan(value, |_, c, depth| {
+		if c == sep && depth == 0 {
+			needs_quotes = true;
+		}
+	});
+	if !needs_quotes && depth == 0 && !in_quotes {
+		return Cow::Borrowed(value);
+	}
+	let mut quoted = String::with_capacity(value.len() + 2);
+	quoted.push('"');
+	for c in value.chars() {
+		if matches!(c, '"' | '\\') {
+			quoted.push('\\');
+		}
+		quoted.push(c);
+	}
+	quoted.push('"');
+	Cow::Owned(quoted)
+}
+
+/// Inverse of [`quote`]: `value` itself, unless it starts with a quote.
+pub fn unquote(value: &str) -> Result<Cow<'_, str>, CompactParseError> {
+	let Some(rest) = value.strip_prefix('"') else {
+		return Ok(Cow::Borrowed(value));
+	};
+	let mut unquoted = String::with_capacity(rest.len());
+	let mut chars = rest.char_indices();
+	while let Some((i, c)) = chars.next() {
+		match c {
+			'\\' => match chars.next() {
+				Some((_, escaped @ ('"' | '\\'))) => unquoted.push(escaped),
+				Some((j, other)) => return Err(CompactParseError::new(value, 1 + i, j - i + other.len_utf8(), "`\\\"` or `\\\\` for an escape")),
+				None => break,
+			},
+			'"' if i + 1 == rest.len() => return Ok(Cow::Owned(unquoted)),
+			'"' => return Err(CompactParseError::new(value, 2 + i, rest.len() - i - 1, "nothing after the closing quote")),
+			c => unquoted.push(c),
+		}
+	}
+	Err(CompactParseError::missing(value, "the closing quote"))
+}
+
+/// Parses `value`, a part of `input`, [`unquote`]d; `ty` names what it should be, for the error.
+pub fn parse_value<T>(input: &str, value: &str, ty: &str) -> Result<T, CompactParseError>
+where
+	T: FromStr,
+	T::Err: fmt::Display + 'static, {
+	let at = offset(input, value);
+	let unquoted = unquote(value).map_err(|e| e.within(input, at))?;
+	let inner_at = at + usize::from(matches!(unquoted, Cow::Owned(_)));
+	unquoted.parse::<T>().map_err(|e| match (&e as &dyn Any).downcast_ref::<CompactParseError>() {
+		// Offsets past an escape are off by the escapes, which is close enough to point at it.
+		Some(nested) => nested.clone().within(input, inner_at),
+		None => CompactParseError::at(input, value, format!("a `{ty}`")).reason(e),
+	})
+}
+
+/// `[a,b,c]`, each item quoted as needed.
+pub fn list<T: fmt::Display>(items: impl IntoIterator<Item = T>) -> String {
+	let items: Vec<String> = items.into_iter().map(|item| quote(&item.to_string(), ',').into_owned()).collect();
+	format!("[{}]", items.join(","))
+}
+
+/// The items of `value`, a `[a,b,c]` part of `input`, as written: each is [`unquote`]d by whatever parses it, so
+/// nested lists stay intact.
+pub fn parse_list<'a>(input: &str, value: &'a str) -> Result<Vec<&'a str>, CompactParseError> {
+	let inner = value
+		.strip_prefix('[')
+		.and_then(|s| s.strip_suffix(']'))
+		.ok_or_else(|| CompactParseError::at(input, value, "a list in `[...]`"))?;
+	Ok(split(inner, ','))
+}
+
+/// Which of `keys` `param` starts with, and the rest of it. Keys are checked to be prefix-free at compile time, so
+/// at most one matches.
+pub fn split_key<'k, 'p>(param: &'p str, keys: &[&'k str]) -> Option<(&'k str, &'p str)> {
+	keys.iter().find_map(|key| param.strip_prefix(key).map(|value| (*key, value)))
+}
+
+/// Byte offset of `part` in `input`, which it's a subslice of.
+pub fn offset(input: &str, part: &str) -> usize {
+	let offset = (part.as_ptr() as usize).wrapping_sub(input.as_ptr() as usize);
+	debug_assert!(offset <= input.len(), "`{part}` is not a part of `{input}`");
+	offset.min(input.len())
+}
+
+/// Calls `f` with each char outside quotes, and the brace/bracket depth it's at. Returns the final depth, and whether
+/// a quote is left open.
+fn scan(s: &str, mut f: impl FnMut(usize, char, i32)) -> (i32, bool) {
+	let mut depth = 0;
+	let mut in_quotes = false;
+	let mut escaped = false;
+	for (i, c) in s.char_indices() {
+		if in_quotes {
+			match c {
+				_ if escaped => escaped = false,
+				'\\' => escaped = true,
+				'"' => in_quotes = false,
+				_ => {}
+			}
+			continue;
+		}
+		match c {
+			'"' => in_quotes = true,
+			'{' | '[' => depth += 1,
+			'}' | ']' => depth -= 1,
+			_ => f(i, c, depth),
+		}
+		// A stray closer would have the rest of the string split as if nested.
+		if depth < 0 {
+			depth = i32::MIN / 2;
+		}
+	}
+	(depth, in_quotes)
+}
+
+/// Building blocks of the `compact_grammar()`s: regexes in the subset shared by the `regex` crate and ECMA-262, so
+/// they also work as a JSON Schema `pattern`.
+///
+/// Values of types the derives know nothing about are matched by [`value`](grammar::value), which follows braces and
+/// brackets two levels deep; anything nested deeper than that fails to match, where it may well parse.
+pub mod grammar {
+	pub const INT: &str = r"[+-]?\d+";
+	pub const UINT: &str = r"\+?\d+";
+	pub const FLOAT: &str = r"[+-]?(?:\d+\.?\d*|\.\d+)(?:[eE][+-]?\d+)?";
+	pub const BOOL: &str = "true|false";
+	const QUOTED: &str = r#""(?:[^"\\]|\\.)*""#;
+
+	/// Any value, between `sep`s.
+	pub fn value(sep: char) -> String {
+		let innermost = r"\{[^{}\[\]]*\}|\[[^{}\[\]]*\]";
+		let nested = format!(r#"(?:[^{{}}\[\]"]|{QUOTED}|{innermost})*"#);
+		let sep = escape(&sep.to_string());
+		format!(r#"{QUOTED}|(?:[^{sep}"{{}}\[\]]|\{{{nested}\}}|\[{nested}\])*"#)
+	}
+
+	/// `[a,b,c]` of `item`s.
+	pub fn list(item: &str) -> String {
+		format!(r"\[(?:(?:{item})(?:,(?:{item}))*)?\]")
+	}
+
+	/// `name:kVALUE:..`, for any of `names`, and fields by their `(key, value)` patterns.
+	pub fn named(names: &[&str], fields: &[(&str, String)]) -> String {
+		let names = alternatives(names.iter().map(|name| escape(name)));
+		match fields.is_empty() {
+			true => format!("{names}(?::_?)?"),
+			false => {
+				let fields = alternatives(fields.iter().map(|(key, value)| format!("{}(?:{value})", escape(key))));
+				format!("{names}(?::_?|(?::{fields})+)?")
+			}
+		}
+	}
+
+	/// `{k=VALUE;..}`, with fields by their `(key, value)` patterns.
+	pub fn map(fields: &[(&str, String)]) -> String {
+		if fields.is_empty() {
+			return r"\{\}".to_owned();
+		}
+		let field = alternatives(fields.iter().map(|(key, value)| format!("{}=(?:{value})", escape(key))));
+		format!(r"\{{{field}(?:;{field})*\}}")
+	}
+
+	/// `(?:a|b|..)`
+	pub fn alternatives(patterns: impl IntoIterator<Item = impl AsRef<str>>) -> String {
+		let patterns: Vec<String> = patterns.into_iter().map(|p| p.as_ref().to_owned()).collect();
+		format!("(?:{})", patterns.join("|"))
+	}
+
+	/// `s`, matched literally.
+	pub fn escape(s: &str) -> String {
+		let mut escaped = String::with_capacity(s.len());
+		for c in s.chars() {
+			if r"\.+*?()|[]{}^$".contains(c) {
+				escaped.push('\\');
+			}
+			escaped.push(c);
+		}
+		escaped
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn quoting_round_trips_whatever_would_be_split() {
+		for s in ["plain", "", "a:b", "{unbalanced", "close}", "say \"hi\"", "back\\slash", "\"leading"] {
+			let quoted = quote(s, ':');
+			assert_eq!(split(&quoted, ':'), [&*quoted], "{quoted}");
+			assert_eq!(unquote(&quoted).unwrap(), s);
+		}
+		assert_eq!(quote("{t=0.5;s=0.3}", ':'), "{t=0.5;s=0.3}");
+		assert_eq!(quote("a:b", ';'), "a:b");
+		assert_eq!(unquote("\"open").unwrap_err().to_string(), "expected the closing quote at byte 5 of `\"open`, found the end");
+	}
+
+	#[test]
+	fn splits_outside_nesting_and_quotes() {
+		assert_eq!(split(r#"n"a:\"b":p{x=1:2}:l[a,"b]"]"#, ':'), [r#"n"a:\"b""#, "p{x=1:2}", r#"l[a,"b]"]"#]);
+		let input = r#"[a,"b,c",[d,e]]"#;
+		assert_eq!(parse_list(input, input).unwrap(), ["a", r#""b,c""#, "[d,e]"]);
+		assert!(parse_list("[]", "[]").unwrap().is_empty());
+		assert_eq!(list(["a", "b,c", ""]), r#"[a,"b,c",""]"#);
+	}
+
+	#[test]
+	fn value_errors_point_into_the_whole_input() {
+		let input = "order:c5x";
+		let err = parse_value::<u32>(input, &input[7..], "u32").unwrap_err();
+		assert_eq!((err.offset, err.len, err.found()), (7, 2, "5x"));
+		assert_eq!(err.to_string(), "expected a `u32` at byte 7 of `order:c5x`, found `5x`: invalid digit found in string");
+
+		#[derive(Debug)]
+		struct Nested;
+		impl FromStr for Nested {
+			type Err = CompactParseError;
+
+			fn from_str(s: &str) -> Result<Self, Self::Err> {
+				Err(CompactParseError::new(s, 3, 1, "a digit"))
+			}
+		}
+		let input = r#"outer:n"ts:x""#;
+		let err = parse_value::<Nested>(input, &input[7..], "Nested").unwrap_err();
+		assert_eq!((err.offset, err.found(), err.expected.as_str()), (11, "x", "a digit"));
+	}
+
+	#[cfg(feature = "miette")]
+	#[test]
+	fn renders_through_miette() {
+		let err = CompactParseError::new("ts:p0.5:q1", 8, 2, "a field keyed by any of [\"p\", \"s\"]");
+		let mut rendered = String::new();
+		miette::GraphicalReportHandler::new_themed(miette::GraphicalTheme::unicode_nocolor())
+			.render_report(&mut rendered, &err)
+			.unwrap();
+		assert!(rendered.contains("ts:p0.5:q1"), "{rendered}");
+		assert!(rendered.contains("expected a field keyed by any of [\"p\", \"s\"]"), "{rendered}");
+	}
+}
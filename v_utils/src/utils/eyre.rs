@@ -1,4 +1,4 @@
-use std::fmt::Write;
+use std::{fmt::Write, io::IsTerminal as _};
 
 use sealed::ExitCode;
 
@@ -37,6 +37,112 @@ impl From<Sysexit> for i32 {
 	}
 }
 
+impl SysexitCode for std::io::Error {
+	fn sysexit(&self) -> Sysexit {
+		io_sysexit(self.kind())
+	}
+}
+
+fn io_sysexit(kind: std::io::ErrorKind) -> Sysexit {
+	use std::io::ErrorKind::*;
+	match kind {
+		NotFound => Sysexit::NoInput,
+		PermissionDenied => Sysexit::NoPerm,
+		AlreadyExists | ReadOnlyFilesystem => Sysexit::CantCreat,
+		InvalidData | UnexpectedEof => Sysexit::DataErr,
+		InvalidInput => Sysexit::Usage,
+		ConnectionRefused | HostUnreachable | NetworkUnreachable | NetworkDown | AddrNotAvailable => Sysexit::Unavailable,
+		TimedOut | Interrupted | WouldBlock | ConnectionReset | ConnectionAborted | NotConnected | BrokenPipe => Sysexit::TempFail,
+		Unsupported | OutOfMemory => Sysexit::OsErr,
+		_ => Sysexit::IoErr,
+	}
+}
+
+macro_rules! impl_data_err {
+	($($t:ty),* $(,)?) => {
+		$(impl SysexitCode for $t {
+			fn sysexit(&self) -> Sysexit {
+				Sysexit::DataErr
+			}
+		})*
+	};
+}
+impl_data_err!(
+	std::num::ParseIntError,
+	std::num::ParseFloatError,
+	std::str::ParseBoolError,
+	std::char::ParseCharError,
+	std::net::AddrParseError,
+	std::str::Utf8Error,
+	std::string::FromUtf8Error,
+);
+#[cfg(feature = "cli")]
+impl_data_err!(toml::de::Error);
+
+/// Malformed input is DataErr; when reading it failed instead, as for [`std::io::Error`].
+impl SysexitCode for serde_json::Error {
+	fn sysexit(&self) -> Sysexit {
+		self.io_error_kind().map_or(Sysexit::DataErr, io_sysexit)
+	}
+}
+
+#[cfg(feature = "cli")]
+impl SysexitCode for config::ConfigError {
+	fn sysexit(&self) -> Sysexit {
+		Sysexit::Config
+	}
+}
+
+/// TempFail for what a retry could fix (timeouts, refused connections, 429 and 5xx), Unavailable otherwise.
+#[cfg(feature = "reqwest")]
+impl SysexitCode for reqwest::Error {
+	fn sysexit(&self) -> Sysexit {
+		#[cfg(not(target_arch = "wasm32"))]
+		let connect = self.is_connect();
+		#[cfg(target_arch = "wasm32")]
+		let connect = false;
+		let retryable_status = self.status().is_some_and(|s| s == reqwest::StatusCode::TOO_MANY_REQUESTS || s.is_server_error());
+		match self.is_timeout() || connect || retryable_status {
+			true => Sysexit::TempFail,
+			false => Sysexit::Unavailable,
+		}
+	}
+}
+
+/// The code of the outermost error down `report`'s chain that has a built-in mapping.
+///
+/// Only the types above are recognized: a user's `SysexitCode` impl is lost once its error is type-erased into a
+/// `Report`, so pass such errors to [`exit_on_error`] as themselves.
+fn chain_sysexit(report: &eyre::Report) -> Option<Sysexit> {
+	report.chain().find_map(builtin_sysexit)
+}
+
+fn builtin_sysexit(e: &(dyn std::error::Error + 'static)) -> Option<Sysexit> {
+	macro_rules! downcast {
+		($($t:ty),* $(,)?) => {
+			$(if let Some(e) = e.downcast_ref::<$t>() {
+				return Some(e.sysexit());
+			})*
+		};
+	}
+	downcast!(
+		std::io::Error,
+		std::num::ParseIntError,
+		std::num::ParseFloatError,
+		std::str::ParseBoolError,
+		std::char::ParseCharError,
+		std::net::AddrParseError,
+		std::str::Utf8Error,
+		std::string::FromUtf8Error,
+		serde_json::Error,
+	);
+	#[cfg(feature = "cli")]
+	downcast!(toml::de::Error, config::ConfigError, crate::__internal::SettingsError);
+	#[cfg(feature = "reqwest")]
+	downcast!(reqwest::Error);
+	None
+}
+
 pub fn format_eyre_chain_for_user(e: eyre::Report) -> String {
 	let mut s = String::new();
 
@@ -57,34 +163,153 @@ pub fn format_eyre_chain_for_user(e: eyre::Report) -> String {
 }
 
 mod sealed {
-	use super::SysexitCode;
+	use super::{Sysexit, SysexitCode};
 
+	/// `None` for errors without a `SysexitCode` impl of their own.
 	pub trait ExitCode {
-		fn exit_code(&self) -> i32;
+		fn exit_code(&self) -> Option<Sysexit>;
 	}
 	impl<E> ExitCode for E {
-		default fn exit_code(&self) -> i32 {
-			7
+		default fn exit_code(&self) -> Option<Sysexit> {
+			None
 		}
 	}
 	impl<E: SysexitCode> ExitCode for E {
-		fn exit_code(&self) -> i32 {
-			self.sysexit().into()
+		fn exit_code(&self) -> Option<Sysexit> {
+			Some(self.sysexit())
 		}
 	}
 }
 
+/// Exits with the error's [`Sysexit`] code, if its type has one. Otherwise, the first built-in mapping found down its
+/// `eyre` chain (e.g. an `io::Error` wrapped into a `Report`), falling back to 7.
+///
+/// The error goes to stderr as [`format_eyre_chain_for_user`], without the colors if stderr isn't a terminal. With
+/// `--error-format json` among the args (declare it in your CLI, so parsing the rest doesn't reject it) or
+/// `<BIN>_ERROR_FORMAT=json` (the binary's name, shouty, so `MY_APP_ERROR_FORMAT` for `my-app`), it's one line of JSON
+/// instead, for whatever runs the program to parse:
+/// `{"code": 66, "sysexit": "NoInput", "chain": ["outermost", .., "root cause"], "backtrace": "..", "spantrace": ".."}`.
+/// `sysexit` is `null` for the fallback code; `backtrace` and `spantrace` are only there when captured.
 pub fn exit_on_error<T, E: Into<eyre::Report>>(r: Result<T, E>) -> T {
 	match r {
 		Ok(t) => t,
 		Err(e) => {
-			let code = e.exit_code();
-			eprintln!("{}", format_eyre_chain_for_user(e.into()));
+			let sysexit = e.exit_code();
+			let report = e.into();
+			let sysexit = sysexit.or_else(|| chain_sysexit(&report));
+			let code = sysexit.map_or(7, i32::from);
+			let bin = std::env::current_exe().ok().and_then(|exe| Some(exe.file_stem()?.to_string_lossy().into_owned())).unwrap_or_default();
+			match ErrorFormat::from_env(std::env::args(), std::env::var(ErrorFormat::var_name(&bin)).ok()) {
+				ErrorFormat::Json => eprintln!("{}", error_json(&report, sysexit, code)),
+				ErrorFormat::Human => {
+					let s = format_eyre_chain_for_user(report);
+					match std::io::stderr().is_terminal() {
+						true => eprintln!("{s}"),
+						false => eprintln!("{}", strip_ansi(&s)),
+					}
+				}
+			}
 			std::process::exit(code);
 		}
 	}
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ErrorFormat {
+	Human,
+	Json,
+}
+
+impl ErrorFormat {
+	/// The `--error-format` flag (`--error-format json` or `--error-format=json`) over the env var.
+	fn from_env<I: IntoIterator<Item = String>>(args: I, var: Option<String>) -> Self {
+		let mut args = args.into_iter();
+		let mut flag = None;
+		while let Some(arg) = args.next() {
+			if arg == "--error-format" {
+				flag = args.next();
+			} else if let Some(value) = arg.strip_prefix("--error-format=") {
+				flag = Some(value.to_owned());
+			}
+		}
+		match flag.or(var).as_deref() {
+			Some("json") => Self::Json,
+			_ => Self::Human,
+		}
+	}
+
+	/// `<BIN>_ERROR_FORMAT`, namespaced like `<CARGO_PKG_NAME>_EXTEND_POLICY`. Binaries take their package's name
+	/// unless `[[bin]]` says otherwise.
+	fn var_name(bin: &str) -> String {
+		format!("{}_ERROR_FORMAT", bin.to_uppercase().replace('-', "_"))
+	}
+}
+
+fn error_json(report: &eyre::Report, sysexit: Option<Sysexit>, code: i32) -> serde_json::Value {
+	let mut json = serde_json::json!({
+		"code": code,
+		"sysexit": sysexit.map(|s| format!("{s:?}")),
+		"chain": report.chain().map(|e| e.to_string()).collect::<Vec<_>>(),
+	});
+	let (backtrace, spantrace) = handler_traces(report);
+	if let Some(backtrace) = backtrace {
+		json["backtrace"] = backtrace.into();
+	}
+	if let Some(spantrace) = spantrace.or_else(|| chain_span_trace(report)) {
+		json["spantrace"] = spantrace.into();
+	}
+	json
+}
+
+/// Backtrace and span trace, as captured by `color_eyre`'s handler.
+#[cfg(feature = "color-eyre")]
+fn handler_traces(report: &eyre::Report) -> (Option<String>, Option<String>) {
+	let Some(handler) = report.handler().downcast_ref::<color_eyre::Handler>() else {
+		return (None, None);
+	};
+	let spantrace = handler.span_trace().filter(|s| s.status() == tracing_error::SpanTraceStatus::CAPTURED);
+	(handler.backtrace().map(|b| format!("{b:?}")), spantrace.map(|s| s.to_string()))
+}
+
+#[cfg(not(feature = "color-eyre"))]
+fn handler_traces(_report: &eyre::Report) -> (Option<String>, Option<String>) {
+	(None, None)
+}
+
+/// From an error down the chain carrying one, e.g. wrapped with `tracing_error::InstrumentError`.
+#[cfg(feature = "tracing")]
+fn chain_span_trace(report: &eyre::Report) -> Option<String> {
+	use tracing_error::ExtractSpanTrace as _;
+	let spantrace = report.chain().find_map(|e| e.span_trace())?;
+	(spantrace.status() == tracing_error::SpanTraceStatus::CAPTURED).then(|| spantrace.to_string())
+}
+
+#[cfg(not(feature = "tracing"))]
+fn chain_span_trace(_report: &eyre::Report) -> Option<String> {
+	None
+}
+
+/// Drops ANSI escape sequences (CSI ones like colors, and two-byte escapes).
+fn strip_ansi(s: &str) -> String {
+	let mut out = String::with_capacity(s.len());
+	let mut chars = s.chars();
+	while let Some(c) = chars.next() {
+		if c != '\x1b' {
+			out.push(c);
+			continue;
+		}
+		if chars.next() == Some('[') {
+			// Parameters and intermediates, up to the final byte in `@`..=`~`.
+			for c in chars.by_ref() {
+				if ('@'..='~').contains(&c) {
+					break;
+				}
+			}
+		}
+	}
+	out
+}
+
 /// Constructs `eyre::Report` with capped size
 pub fn report_msg(s: String) -> eyre::Report {
 	let truncated_message = truncate_msg(&s);
@@ -107,10 +332,76 @@ pub fn truncate_msg<S: AsRef<str>>(s: S) -> String {
 		let end_cut = &lines[lines.len() - (MAX_LINES / 2)..];
 		format!("{}{truncation_message}{}", start_cut.join("\n"), end_cut.join("\n"))
 	} else if s.chars().count() > MAX_LINES * CHARS_IN_A_LINE {
-		let start_cut = &s[..(MAX_LINES * CHARS_IN_A_LINE / 2)];
-		let end_cut = &s[s.len() - (MAX_LINES * CHARS_IN_A_LINE / 2)..];
+		// In chars rather than bytes, so multi-byte text isn't cut mid-character.
+		let half = MAX_LINES * CHARS_IN_A_LINE / 2;
+		let start_cut: String = s.chars().take(half).collect();
+		let end_cut: String = s.chars().skip(s.chars().count() - half).collect();
 		format!("{start_cut}{truncation_message}{end_cut}")
 	} else {
 		s.to_owned()
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use eyre::WrapErr as _;
+
+	use super::*;
+
+	#[test]
+	fn io_errors_map_by_kind() {
+		let code = |kind| i32::from(std::io::Error::from(kind).sysexit());
+		assert_eq!(code(std::io::ErrorKind::NotFound), 66);
+		assert_eq!(code(std::io::ErrorKind::PermissionDenied), 77);
+		assert_eq!(code(std::io::ErrorKind::TimedOut), 75);
+		assert_eq!(code(std::io::ErrorKind::Other), 74);
+	}
+
+	#[test]
+	fn report_chain_is_searched_for_a_mapping() {
+		let read = std::fs::read_to_string("/nonexistent/v_utils/config.toml").wrap_err("reading config").wrap_err("starting up");
+		assert!(matches!(chain_sysexit(&read.unwrap_err()), Some(Sysexit::NoInput)));
+
+		let parse = "12x".parse::<u32>().map_err(eyre::Report::from).wrap_err("parsing --port");
+		assert!(matches!(chain_sysexit(&parse.unwrap_err()), Some(Sysexit::DataErr)));
+
+		assert!(chain_sysexit(&eyre::eyre!("no mapping")).is_none());
+	}
+
+	#[test]
+	fn own_sysexit_code_wins_over_the_fallback() {
+		let parse_err = "x".parse::<f64>().unwrap_err();
+		assert!(matches!(parse_err.exit_code(), Some(Sysexit::DataErr)));
+		assert!(eyre::eyre!("no mapping").exit_code().is_none());
+	}
+
+	#[test]
+	fn error_format_flag_overrides_env() {
+		let args = |a: &[&str]| a.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+		assert_eq!(ErrorFormat::from_env(args(&["bot", "--error-format", "json"]), None), ErrorFormat::Json);
+		assert_eq!(ErrorFormat::from_env(args(&["bot", "--error-format=human"]), Some("json".to_owned())), ErrorFormat::Human);
+		assert_eq!(ErrorFormat::from_env(args(&["bot"]), Some("json".to_owned())), ErrorFormat::Json);
+		assert_eq!(ErrorFormat::from_env(args(&["bot"]), None), ErrorFormat::Human);
+		assert_eq!(ErrorFormat::var_name("my-app"), "MY_APP_ERROR_FORMAT");
+	}
+
+	#[test]
+	fn json_error_carries_code_and_chain() {
+		let report = std::fs::read_to_string("/nonexistent/v_utils/config.toml").wrap_err("reading config").unwrap_err();
+		let sysexit = chain_sysexit(&report);
+		let json = error_json(&report, sysexit, sysexit.map_or(7, i32::from));
+		assert_eq!(json["code"], 66);
+		assert_eq!(json["sysexit"], "NoInput");
+		let chain = json["chain"].as_array().unwrap();
+		assert_eq!(chain.len(), 2);
+		assert_eq!(chain[0], "reading config");
+		assert!(chain[1].as_str().unwrap().contains("No such file"), "{json}");
+		assert!(json.get("backtrace").is_none(), "{json}");
+	}
+
+	#[test]
+	fn strips_ansi_for_non_terminals() {
+		assert_eq!(strip_ansi("\x1b[31mError\x1b[0m: no \x1b[1;4mbold\x1b[0m here"), "Error: no bold here");
+		assert_eq!(strip_ansi("plain ünïcode"), "plain ünïcode");
+	}
+}
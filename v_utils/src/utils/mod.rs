@@ -4,6 +4,8 @@ pub use collections::*;
 pub mod math;
 pub use math::*;
 
+pub mod compact;
+
 pub mod eyre;
 pub use eyre::*;
 
@@ -48,8 +50,10 @@ macro_rules! elog {
 
 #[cfg(feature = "tracing")]
 pub mod tracing;
+#[cfg(all(feature = "tracing", not(target_arch = "wasm32"), feature = "xdg"))]
+pub use tracing::xdg_log_path;
 #[cfg(feature = "tracing")]
-pub use tracing::*;
+pub use tracing::{LogDestination, LogDestinationKind, current_log_path, init_subscriber, set_directives, shutdown_guard};
 
 /// Sets up error handling (color_eyre, miette) and tracing subscriber for client-side applications.
 ///
@@ -88,9 +92,10 @@ macro_rules! clientside {
 		if std::env::var("__IS_INTEGRATION_TEST").is_ok() {
 			// SAFETY: Called at program start before any other threads are spawned
 			unsafe { std::env::set_var("LOG_DIRECTIVES", concat!("debug,", env!("CARGO_PKG_NAME"), "=debug")) };
-			v_utils::utils::init_subscriber(v_utils::utils::LogDestination::default());
+			v_utils::utils::init_subscriber(v_utils::utils::LogDestination::default().service_name(env!("CARGO_PKG_NAME")));
 		} else {
 			let mut dest = v_utils::utils::LogDestination::xdg(env!("CARGO_PKG_NAME"))
+				.service_name(env!("CARGO_PKG_NAME"))
 				.stderr_errors(true)
 				.compiled_directives(option_env!("LOG_DIRECTIVES"));
 			if let Some(fname) = $fname {
@@ -113,11 +118,16 @@ macro_rules! clientside {
 		eprintln!("[v_utils] Warning: `xdg` feature not enabled, logging to stdout instead of file. Add `xdg` feature to v_utils dependency to enable file logging.");
 		color_eyre::install().unwrap();
 		miette::set_hook(Box::new(|_| Box::new(miette::MietteHandlerOpts::new().terminal_links(true).context_lines(3).build()))).expect("miette hook already set");
-		v_utils::utils::init_subscriber(v_utils::utils::LogDestination::default().compiled_directives(option_env!("LOG_DIRECTIVES")));
+		v_utils::utils::init_subscriber(
+			v_utils::utils::LogDestination::default()
+				.service_name(env!("CARGO_PKG_NAME"))
+				.compiled_directives(option_env!("LOG_DIRECTIVES")),
+		);
 	};
 }
 
-/// **Warning**: Consider using `strum` crate instead - this macro is likely redundant for most use cases.
+/// Superseded by `#[derive(StrEnum)]`, which also takes case styles, aliases and case-insensitive input.
+#[deprecated(note = "Use #[derive(StrEnum)] instead")]
 #[macro_export]
 macro_rules! define_str_enum {
   ($(#[$meta:meta])* $vis:vis enum $name:ident {
@@ -0,0 +1,56 @@
+//! Noticing edits to the files a running process is configured from: the log directives, and the span thresholds.
+
+use std::{
+	io,
+	path::PathBuf,
+	thread,
+	time::{Duration, SystemTime},
+};
+
+use tracing::{error, info};
+
+use super::{
+	CARGO_DIRECTIVES_PATH, DIRECTIVES_FILENAME,
+	live_directives::{self, FilterHandle},
+};
+
+/// How often the watched files are checked for changes
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// [`register`](live_directives::register)s `handle`, then re-resolves the directives whenever one of the files it could
+/// have taken them from is created, edited or removed.
+pub(super) fn directives(handle: FilterHandle, log_dir: Option<PathBuf>, compiled_directives: Option<&'static str>) {
+	live_directives::register(handle.clone());
+
+	let log_dir_path = log_dir.map(|d| d.join(DIRECTIVES_FILENAME));
+	let watched: Vec<PathBuf> = std::iter::once(PathBuf::from(CARGO_DIRECTIVES_PATH)).chain(log_dir_path.clone()).collect();
+
+	let spawned = on_change("log-directives-watcher", watched, move || {
+		let (directives, source) = super::resolve_directives(log_dir_path.as_deref(), compiled_directives);
+		let source = source.unwrap_or_else(|| "defaults".to_owned());
+		match live_directives::apply(&handle, &directives) {
+			Ok(()) => info!("Reloaded log directives from `{source}`:\n{directives}"),
+			Err(e) => error!("Keeping the current log directives, `{source}` didn't apply: {e}"),
+		}
+	});
+	if let Err(e) = spawned {
+		error!("Couldn't start watching log directives files, they'll only be read at init: {e}");
+	}
+}
+
+/// Calls `f` from a thread named `name` whenever one of `watched` is created, edited or removed.
+pub(super) fn on_change(name: &str, watched: Vec<PathBuf>, mut f: impl FnMut() + Send + 'static) -> io::Result<()> {
+	let stamps = move || -> Vec<Option<(SystemTime, u64)>> { watched.iter().map(|p| std::fs::metadata(p).ok().and_then(|m| Some((m.modified().ok()?, m.len())))).collect() };
+	thread::Builder::new().name(name.to_owned()).spawn(move || {
+		let mut last = stamps();
+		loop {
+			thread::sleep(POLL_INTERVAL);
+			let current = stamps();
+			if current != last {
+				last = current;
+				f();
+			}
+		}
+	})?;
+	Ok(())
+}
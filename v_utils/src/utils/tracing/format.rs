@@ -0,0 +1,211 @@
+//! Renderings of the log layers.
+
+use std::fmt;
+
+use serde::{Serialize, Serializer, ser::SerializeMap};
+use tracing::{
+	Event, Subscriber,
+	field::{Field, Visit},
+};
+use tracing_subscriber::{
+	Layer,
+	fmt::{
+		FmtContext, FormatEvent, FormatFields, FormattedFields, MakeWriter,
+		format::{JsonFields, Writer},
+		time::{FormatTime, SystemTime},
+	},
+	registry::{LookupSpan, SpanRef},
+};
+
+use super::LogFormat;
+
+/// Event timestamps: RFC 3339 in UTC, or in the zone set through [`LogDestination::timezone`](super::LogDestination::timezone).
+#[derive(Clone, Debug, Default)]
+pub(crate) struct LogTimer(pub Option<jiff::tz::TimeZone>);
+
+impl FormatTime for LogTimer {
+	fn format_time(&self, w: &mut Writer<'_>) -> fmt::Result {
+		match &self.0 {
+			None => SystemTime.format_time(w),
+			Some(tz) => write!(w, "{}", jiff::Timestamp::now().to_zoned(tz.clone()).strftime("%Y-%m-%dT%H:%M:%S%.6f%:z")),
+		}
+	}
+}
+
+/// `locations` adds the source file and line of each event.
+pub(crate) fn fmt_layer<S, W>(format: LogFormat, timer: LogTimer, writer: W, ansi: bool, locations: bool) -> Box<dyn Layer<S> + Send + Sync>
+where
+	S: Subscriber + for<'a> LookupSpan<'a>,
+	W: for<'w> MakeWriter<'w> + Send + Sync + 'static, {
+	let layer = tracing_subscriber::fmt::layer()
+		.with_writer(writer)
+		.with_timer(timer.clone())
+		.with_ansi(ansi)
+		.with_file(locations)
+		.with_line_number(locations);
+	match format {
+		LogFormat::PrettyJson => layer.json().pretty().boxed(),
+		LogFormat::JsonLines => layer.json().boxed(),
+		LogFormat::IndentedJson => layer.fmt_fields(JsonFields::new()).event_format(IndentedJson { timer, locations }).boxed(),
+		LogFormat::Text => layer.boxed(),
+		LogFormat::Compact => layer.compact().boxed(),
+	}
+}
+
+/// [`LogFormat::IndentedJson`]: the object [`LogFormat::JsonLines`] writes, serialized straight into serde_json's pretty printer.
+struct IndentedJson {
+	timer: LogTimer,
+	locations: bool,
+}
+
+impl<S, N> FormatEvent<S, N> for IndentedJson
+where
+	S: Subscriber + for<'a> LookupSpan<'a>,
+	N: for<'w> FormatFields<'w> + 'static,
+{
+	fn format_event(&self, ctx: &FmtContext<'_, S, N>, mut writer: Writer<'_>, event: &Event<'_>) -> fmt::Result {
+		let mut timestamp = String::new();
+		self.timer.format_time(&mut Writer::new(&mut timestamp))?;
+		let meta = event.metadata();
+		let span_json = |span: &SpanRef<'_, S>| {
+			let ext = span.extensions();
+			let mut fields = ext
+				.get::<FormattedFields<N>>()
+				.and_then(|f| serde_json::from_str::<serde_json::Map<String, serde_json::Value>>(f).ok())
+				.unwrap_or_default();
+			fields.insert("name".to_owned(), span.name().into());
+			fields
+		};
+
+		let mut out = Vec::new();
+		let mut serializer = serde_json::Serializer::pretty(&mut out);
+		let mut map = (&mut serializer).serialize_map(None).map_err(|_| fmt::Error)?;
+		let entries = || -> Result<(), serde_json::Error> {
+			map.serialize_entry("timestamp", timestamp.trim())?;
+			map.serialize_entry("level", meta.level().as_str())?;
+			map.serialize_entry("fields", &EventFields(event))?;
+			map.serialize_entry("target", meta.target())?;
+			if self.locations {
+				if let Some(file) = meta.file() {
+					map.serialize_entry("filename", file)?;
+				}
+				if let Some(line) = meta.line() {
+					map.serialize_entry("line_number", &line)?;
+				}
+			}
+			if let Some(span) = ctx.lookup_current() {
+				map.serialize_entry("span", &span_json(&span))?;
+			}
+			if let Some(scope) = ctx.event_scope() {
+				map.serialize_entry("spans", &scope.from_root().map(|span| span_json(&span)).collect::<Vec<_>>())?;
+			}
+			map.end()
+		};
+		entries().map_err(|_| fmt::Error)?;
+		writeln!(writer, "{}", std::str::from_utf8(&out).map_err(|_| fmt::Error)?)
+	}
+}
+
+/// An event's fields as a JSON object, in the order they were recorded.
+struct EventFields<'a>(&'a Event<'a>);
+
+impl Serialize for EventFields<'_> {
+	fn serialize<Ser: Serializer>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error> {
+		let mut map = serializer.serialize_map(None)?;
+		let mut visitor = FieldsVisitor { map: &mut map, result: Ok(()) };
+		self.0.record(&mut visitor);
+		visitor.result?;
+		map.end()
+	}
+}
+
+struct FieldsVisitor<'a, M: SerializeMap> {
+	map: &'a mut M,
+	/// The first error, after which the rest of the fields are skipped
+	result: Result<(), M::Error>,
+}
+
+impl<M: SerializeMap> FieldsVisitor<'_, M> {
+	fn entry<T: Serialize + ?Sized>(&mut self, field: &Field, value: &T) {
+		if self.result.is_ok() {
+			self.result = self.map.serialize_entry(field.name(), value);
+		}
+	}
+}
+
+impl<M: SerializeMap> Visit for FieldsVisitor<'_, M> {
+	fn record_f64(&mut self, field: &Field, value: f64) {
+		self.entry(field, &value);
+	}
+
+	fn record_i64(&mut self, field: &Field, value: i64) {
+		self.entry(field, &value);
+	}
+
+	fn record_u64(&mut self, field: &Field, value: u64) {
+		self.entry(field, &value);
+	}
+
+	fn record_bool(&mut self, field: &Field, value: bool) {
+		self.entry(field, &value);
+	}
+
+	fn record_str(&mut self, field: &Field, value: &str) {
+		self.entry(field, value);
+	}
+
+	fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+		self.entry(field, &format!("{value:?}"));
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use tracing_subscriber::layer::SubscriberExt as _;
+
+	use super::*;
+	use crate::utils::tracing::tests::TestSink;
+
+	fn render(format: LogFormat, timer: LogTimer) -> String {
+		let sink = TestSink::default();
+		let subscriber = tracing_subscriber::registry().with(fmt_layer(format, timer, sink.clone(), false, false));
+		tracing::subscriber::with_default(subscriber, || {
+			let _span = tracing::info_span!("order", id = 7).entered();
+			tracing::info!(qty = 3, "filled");
+		});
+		sink.output()
+	}
+
+	#[test]
+	fn json_lines_is_one_object_per_event() {
+		let out = render(LogFormat::JsonLines, LogTimer::default());
+		assert_eq!(out.lines().count(), 1, "got:\n{out}");
+		let event: serde_json::Value = serde_json::from_str(out.trim_end()).unwrap();
+		assert_eq!(event["fields"]["message"], "filled");
+		assert_eq!(event["fields"]["qty"], 3);
+		assert_eq!(event["span"]["name"], "order");
+	}
+
+	#[test]
+	fn indented_json_is_json_lines_indented() {
+		let parse = |out: &str| {
+			let mut event: serde_json::Value = serde_json::from_str(out).unwrap();
+			event.as_object_mut().unwrap().remove("timestamp");
+			event
+		};
+		let indented = render(LogFormat::IndentedJson, LogTimer::default());
+		assert!(indented.lines().count() > 1, "got:\n{indented}");
+		assert_eq!(parse(&indented), parse(&render(LogFormat::JsonLines, LogTimer::default())));
+		// Fields stay in the order they were recorded, rather than serde_json's sorted one.
+		assert!(indented.find("\"message\"").unwrap() < indented.find("\"qty\"").unwrap(), "got:\n{indented}");
+	}
+
+	#[test]
+	fn text_keeps_span_context_and_timezone() {
+		let out = render(LogFormat::Text, LogTimer(Some(jiff::tz::TimeZone::fixed(jiff::tz::offset(3)))));
+		assert_eq!(out.lines().count(), 1, "got:\n{out}");
+		assert!(out.contains("+03:00"), "got:\n{out}");
+		assert!(out.contains("order{id=7}"), "got:\n{out}");
+		assert!(out.contains("filled qty=3"), "got:\n{out}");
+	}
+}
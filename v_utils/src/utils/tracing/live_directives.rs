@@ -0,0 +1,70 @@
+//! Changing the log directives of a running process.
+//!
+//! The `EnvFilter` built by [`init_subscriber`](super::init_subscriber) sits behind a `reload::Layer`. Off the browser, a
+//! watcher thread polls the directives files and re-applies them when they change; [`set_directives`] does the same on
+//! demand.
+
+use std::sync::OnceLock;
+
+use tracing_subscriber::{EnvFilter, Registry, filter::ParseError, reload};
+
+pub(super) type FilterHandle = reload::Handle<EnvFilter, Registry>;
+
+static FILTER_HANDLE: OnceLock<FilterHandle> = OnceLock::new();
+
+#[derive(Debug, thiserror::Error)]
+pub enum DirectivesError {
+	#[error("`init_subscriber` hasn't been called, so there is no log filter to update")]
+	NotInitialized,
+	#[error("Error parsing tracing directives:\n```\n{directives}\n```\n{source}")]
+	Parse { directives: String, source: ParseError },
+	#[error(transparent)]
+	Reload(#[from] reload::Error),
+}
+
+/// Replaces the log directives of the running process, e.g. `"info,my_crate::orders=debug"`.
+///
+/// Takes the same syntax as the `_log_directives` file (comma- or newline-separated, `#` comments). On error the
+/// current filter stays in place. The directives files remain watched, so their next change overrides this.
+pub fn set_directives(directives: &str) -> Result<(), DirectivesError> {
+	let handle = FILTER_HANDLE.get().ok_or(DirectivesError::NotInitialized)?;
+	apply(handle, &super::normalize_directives(directives))
+}
+
+pub(super) fn apply(handle: &FilterHandle, directives: &str) -> Result<(), DirectivesError> {
+	let filter = EnvFilter::builder().parse(super::console_directives(directives)).map_err(|source| DirectivesError::Parse {
+		directives: directives.to_owned(),
+		source,
+	})?;
+	handle.reload(filter)?;
+	Ok(())
+}
+
+/// Makes `handle` the filter [`set_directives`] updates.
+pub(super) fn register(handle: FilterHandle) {
+	let _ = FILTER_HANDLE.set(handle);
+}
+
+#[cfg(test)]
+mod tests {
+	use tracing_subscriber::layer::SubscriberExt as _;
+
+	use super::*;
+
+	#[test]
+	fn apply_swaps_the_filter_and_keeps_it_on_error() {
+		let (layer, handle) = reload::Layer::new(EnvFilter::new("info"));
+		let subscriber = tracing_subscriber::registry().with(layer);
+
+		tracing::subscriber::with_default(subscriber, || {
+			assert!(!tracing::enabled!(tracing::Level::DEBUG));
+
+			apply(&handle, "debug").unwrap();
+			assert!(tracing::enabled!(tracing::Level::DEBUG));
+
+			let err = apply(&handle, "my_crate=loud").unwrap_err();
+			assert!(matches!(err, DirectivesError::Parse { .. }), "got: {err}");
+			assert!(tracing::enabled!(tracing::Level::DEBUG));
+		});
+	}
+}
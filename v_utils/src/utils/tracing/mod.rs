@@ -0,0 +1,629 @@
+use std::{
+	borrow::Cow,
+	path::{Path, PathBuf},
+};
+#[cfg(native)]
+use std::{
+	collections::BTreeMap,
+	env::{args_os, current_dir, current_exe, vars_os},
+	io::Write,
+};
+
+use tracing::{error, info, warn};
+#[cfg(native)]
+use tracing_error::ErrorLayer;
+use tracing_subscriber::EnvFilter;
+#[cfg(native)]
+use tracing_subscriber::{layer::SubscriberExt as _, prelude::*};
+
+// Shared by the native and browser inits
+mod live_directives;
+mod options;
+mod otlp;
+mod panic_hook;
+mod redaction;
+pub mod test;
+// Files, stderr, threads and the like, for which the browser has no use
+#[cfg(native)]
+mod file_watch;
+#[cfg(native)]
+pub(crate) mod format;
+#[cfg(native)]
+mod redacted_layer;
+#[cfg(native)]
+mod rotation;
+#[cfg(native)]
+mod routing;
+#[cfg(native)]
+mod span_timing;
+#[cfg(native)]
+mod tokio_console;
+#[cfg(not(native))]
+mod wasm;
+#[cfg(native)]
+use format::{LogTimer, fmt_layer};
+pub use live_directives::{DirectivesError, set_directives};
+pub use options::{LogFormat, Rotation, RotationPolicy, Route, SpanThresholds, SpanThresholdsError, current_log_path};
+#[cfg(feature = "otlp")]
+pub use otlp::OtlpConfig;
+pub use redaction::Redaction;
+use redaction::Redactor;
+#[cfg(native)]
+use rotation::RotatingFileWriter;
+#[cfg(native)]
+use span_timing::SpanTimingLayer;
+#[cfg(not(native))]
+pub use wasm::init_subscriber;
+
+const CARGO_DIRECTIVES_PATH: &str = ".cargo/log_directives";
+const DIRECTIVES_FILENAME: &str = "_log_directives";
+const DEFAULT_DIRECTIVES: &str = "debug,hyper=info,hyper_util=info";
+impl LogDestination {
+	/// Helper for creating File variant
+	pub fn file<P: Into<PathBuf>>(path: P) -> Self {
+		LogDestination {
+			kind: LogDestinationKind::File { path: path.into() },
+			..Default::default()
+		}
+	}
+
+	/// Helper for creating Xdg variant
+	#[cfg(all(not(target_arch = "wasm32"), feature = "xdg"))]
+	pub fn xdg<S: Into<String>>(name: S) -> Self {
+		LogDestination {
+			kind: LogDestinationKind::Xdg { dname: name.into(), fname: None },
+			..Default::default()
+		}
+	}
+
+	/// Set custom filename for Xdg variant (creates `{fname}.log`)
+	#[cfg(all(not(target_arch = "wasm32"), feature = "xdg"))]
+	pub fn fname<S: Into<String>>(mut self, fname: S) -> Self {
+		if let LogDestinationKind::Xdg { dname, .. } = self.kind {
+			self.kind = LogDestinationKind::Xdg { dname, fname: Some(fname.into()) };
+		}
+		self
+	}
+
+	/// Enable/disable ERROR level logging to stderr
+	pub fn stderr_errors(mut self, enabled: bool) -> Self {
+		self.stderr_errors = enabled;
+		self
+	}
+
+	/// Set compile-time embedded directives (takes priority over file-based directives).
+	/// Typically used with `option_env!("LOG_DIRECTIVES")` in the downstream crate.
+	pub fn compiled_directives(mut self, directives: Option<&'static str>) -> Self {
+		self.compiled_directives = directives;
+		self
+	}
+
+	/// Split the log into segments, closing the live one and starting a new one at `rotation`, with [`current_log_path`]
+	/// pointing at the live one (default: none, the log is written at its configured path and truncated past 20GB)
+	pub fn rotate(mut self, rotation: Rotation) -> Self {
+		self.rotation.rotation = Some(rotation);
+		self
+	}
+
+	/// Number of closed log segments to retain next to the live one, with [`rotate`](Self::rotate) (default: 0, i.e. none: each rotation, and each restart, drops the previous segment)
+	pub fn keep(mut self, n: usize) -> Self {
+		self.rotation.keep = n;
+		self
+	}
+
+	/// Gzip closed log segments, with [`rotate`](Self::rotate)
+	pub fn gzip(mut self, enabled: bool) -> Self {
+		self.rotation.gzip = enabled;
+		self
+	}
+
+	/// Format of the log file, or of stdout (default: [`LogFormat::PrettyJson`])
+	pub fn format(mut self, format: LogFormat) -> Self {
+		self.format = format;
+		self
+	}
+
+	/// Format of the stderr layer enabled by [`stderr_errors`](Self::stderr_errors) (default: [`LogFormat::Text`])
+	pub fn stderr_format(mut self, format: LogFormat) -> Self {
+		self.stderr_format = format;
+		self
+	}
+
+	/// Timezone of event timestamps, e.g. `jiff::tz::TimeZone::system()` (default: UTC)
+	pub fn timezone(mut self, tz: jiff::tz::TimeZone) -> Self {
+		self.timezone = Some(tz);
+		self
+	}
+
+	/// Also record panics as ERROR events (target `panic`) with location, thread name, backtrace and span trace, so
+	/// those in background threads reach the log file. Chains onto the panic hook installed at the time, e.g.
+	/// `color_eyre`'s, which then runs as before; being what prints the panic to stderr, the events are kept off the
+	/// [`stderr_errors`](Self::stderr_errors) layer.
+	pub fn panic_hook(mut self, enabled: bool) -> Self {
+		self.panic_hook = enabled;
+		self
+	}
+
+	/// Also write the events of `target` and its submodules to `path`, e.g. `.route("bot::orders", "orders.log")`.
+	/// The main log still gets everything. Relative paths are resolved against the main log's directory.
+	pub fn route<T: Into<String>, P: Into<PathBuf>>(self, target: T, path: P) -> Self {
+		self.route_with(Route::new(target, path))
+	}
+
+	/// [`route`](Self::route), with its own directives, format or rotation
+	pub fn route_with(mut self, route: Route) -> Self {
+		self.routes.push(route);
+		self
+	}
+
+	/// What to mask in the logs (default: values of secret-looking env vars, and oversized fields get truncated)
+	pub fn redact(mut self, redaction: Redaction) -> Self {
+		self.redaction = redaction;
+		self
+	}
+
+	/// Time every closed span, warning about those outliving their target's threshold. A p50/p99 summary per span is
+	/// logged on SIGUSR1 and when the [`shutdown_guard`] drops; the handler chains onto existing ones, but SIGUSR1 no
+	/// longer terminates the process. `.cargo/span_thresholds` or `<log_dir>/_span_thresholds`, if present, take
+	/// precedence over `thresholds` the way the directives files do, and are re-read on change.
+	pub fn span_timing(mut self, thresholds: SpanThresholds) -> Self {
+		self.span_timing = Some(thresholds);
+		self
+	}
+
+	/// Serve [tokio-console](https://github.com/tokio-rs/console) (on `TOKIO_CONSOLE_BIND`, default `127.0.0.1:6669`).
+	/// Its `tokio=trace,runtime=trace` instrumentation is enabled on top of the directives, but kept out of the log
+	/// file and OTLP. Needs the binary built with `--cfg tokio_unstable`. Only the first init in a process starts it.
+	#[cfg(feature = "tokio-console")]
+	pub fn tokio_console(mut self, enabled: bool) -> Self {
+		self.tokio_console = enabled;
+		self
+	}
+
+	/// Name of the app, reported as the OTLP `service.name` unless `OtlpConfig::service_name` or `OTEL_SERVICE_NAME`
+	/// say otherwise. [`clientside!`](crate::clientside) sets it to the calling crate's name.
+	pub fn service_name<S: Into<String>>(mut self, name: S) -> Self {
+		self.service_name = Some(name.into());
+		self
+	}
+
+	/// Export logs and traces over OTLP, whether or not `OTEL_EXPORTER_OTLP_ENDPOINT` is set.
+	/// Hold a [`shutdown_guard`] in `main` so the last batches get flushed.
+	#[cfg(feature = "otlp")]
+	pub fn otlp(mut self, config: OtlpConfig) -> Self {
+		self.otlp = Some(config);
+		self
+	}
+}
+
+/// # Panics (iff ` Some(path)` && `path`'s parent dir doesn't exist || `path` is not writable)
+/// Set "TEST_LOG=1" to redirect to stdout
+///
+/// Log directives are re-read whenever `.cargo/log_directives` or `<log_dir>/_log_directives` change, and can be
+/// swapped programmatically with [`set_directives`].
+///
+/// On wasm32 with the `wasm` feature, a browser-console flavour of this takes its place.
+#[cfg(native)]
+pub fn init_subscriber(log_destination: LogDestination) {
+	let mut logs_during_init: Vec<Box<dyn FnOnce()>> = Vec::new();
+	let compiled_directives = log_destination.compiled_directives;
+	let (format, stderr_format) = (log_destination.format, log_destination.stderr_format);
+	let timer = LogTimer(log_destination.timezone.clone());
+	// Before the directives are resolved, which take the console's into account once it runs.
+	let console_layer = tokio_console::layer(&log_destination);
+	let panic_hook = log_destination.panic_hook;
+	let redactor = Redactor::new(&log_destination.redaction);
+	let otlp_layer = otlp::layer(&log_destination, &mut logs_during_init).map(|layer| tokio_console::without_instrumentation(redactor.wrap(layer)));
+	let (routes, rotation) = (log_destination.routes.clone(), log_destination.rotation);
+	let span_timing = log_destination.span_timing.clone();
+
+	let setup = |make_writer: Box<dyn Fn() -> Box<dyn Write> + Send + Sync>, stderr_errors: bool, log_dir: Option<PathBuf>| {
+		//TEST: if `with_ansi(false)` removes the need for `AnsiEsc` completely
+		let formatting_layer = tokio_console::without_instrumentation(fmt_layer(format, timer.clone(), redactor.writer(make_writer), false, true));
+
+		// Behind a reload layer, so `set_directives` and edits to the directives files apply without a restart.
+		let (env_filter, filter_handle) = tracing_subscriber::reload::Layer::new(filter_with_directives(&mut logs_during_init, log_dir.as_deref(), compiled_directives));
+
+		let error_layer = ErrorLayer::default();
+
+		use tracing_subscriber::filter::{FilterExt as _, LevelFilter, filter_fn};
+
+		let route_layers: Vec<_> = routes
+			.iter()
+			.map(|route| routing::layer(route, log_dir.as_deref(), (format, rotation), timer.clone(), &redactor, &mut logs_during_init))
+			.collect();
+
+		let span_timing_layer = span_timing.map(|thresholds| SpanTimingLayer::install(thresholds, log_dir.as_deref(), &mut logs_during_init));
+
+		// Conditionally create stderr layer (WARN and ERROR go to stderr)
+		let stderr_layer = if stderr_errors {
+			let not_panics = filter_fn(|meta| meta.target() != panic_hook::TARGET);
+			Some(fmt_layer(stderr_format, timer, redactor.writer(std::io::stderr), true, false).with_filter(LevelFilter::WARN.and(not_panics)))
+		} else {
+			None
+		};
+
+		tracing_subscriber::registry()
+			.with(env_filter)
+			.with(console_layer)
+			.with(redactor.layer())
+			.with(formatting_layer)
+			.with(route_layers)
+			.with(stderr_layer)
+			.with(error_layer)
+			.with(span_timing_layer)
+			.with(otlp_layer)
+			.init();
+		file_watch::directives(filter_handle, log_dir, compiled_directives);
+	};
+
+	fn destination_is_path<F, P>(path: P, stderr_errors: bool, rotation: RotationPolicy, setup: F)
+	where
+		P: Into<PathBuf> + Sized,
+		F: FnOnce(Box<dyn Fn() -> Box<dyn Write> + Send + Sync>, bool, Option<PathBuf>), {
+		let path = path.into();
+		let log_dir = path.parent().map(|p| p.to_path_buf());
+
+		let writer = RotatingFileWriter::open(path.clone(), rotation).unwrap_or_else(|e| {
+			panic!(
+				"Couldn't open {} for writing ({e}). If its parent directory doesn't exist, create it manually first",
+				path.display()
+			)
+		});
+
+		setup(
+			Box::new(move || {
+				// Clone the handle, which clones the Arc (not the file handle)
+				Box::new(writer.clone()) as Box<dyn Write>
+			}),
+			stderr_errors,
+			log_dir,
+		);
+	}
+
+	let stderr_errors = log_destination.stderr_errors;
+	match log_destination.kind {
+		LogDestinationKind::File { path } => {
+			destination_is_path(path, stderr_errors, rotation, setup);
+		}
+		LogDestinationKind::Stdout => {
+			setup(Box::new(|| Box::new(std::io::stdout())), false, None);
+		}
+		#[cfg(all(not(target_arch = "wasm32"), feature = "xdg"))]
+		LogDestinationKind::Xdg { dname, fname } => {
+			let associated_state_home = xdg::BaseDirectories::with_prefix(dname).create_state_directory("").unwrap();
+			let log_path = associated_state_home.join(xdg_log_filename(fname.as_deref()));
+			destination_is_path(log_path, stderr_errors, rotation, setup);
+		}
+	};
+
+	for log in logs_during_init {
+		log();
+	}
+	if panic_hook {
+		panic_hook::install();
+	}
+	info!("Starting ...");
+
+	trace_the_init(); //? Should I make this a trace?
+}
+
+/// Flushes what would otherwise be lost as `main` returns: the [span timing](LogDestination::span_timing) summary, and
+/// the OTLP batches still queued. Inert for what isn't enabled, so it can be held unconditionally.
+#[must_use = "flushing happens when the guard is dropped"]
+pub fn shutdown_guard() -> ShutdownGuard {
+	ShutdownGuard { _private: () }
+}
+
+/// See [`shutdown_guard`].
+pub struct ShutdownGuard {
+	_private: (),
+}
+
+impl Drop for ShutdownGuard {
+	fn drop(&mut self) {
+		// Before OTLP shuts down, so the summary gets exported too.
+		#[cfg(native)]
+		span_timing::dump_summary();
+		otlp::shutdown();
+	}
+}
+
+#[derive(Clone, Debug)]
+pub struct LogDestination {
+	pub kind: LogDestinationKind,
+	pub stderr_errors: bool,
+	/// Compile-time embedded directives (set via build.rs). Takes priority over file-based directives.
+	pub compiled_directives: Option<&'static str>,
+	/// Rotation and retention of the log file. Ignored for [`LogDestinationKind::Stdout`].
+	pub rotation: RotationPolicy,
+	pub format: LogFormat,
+	pub stderr_format: LogFormat,
+	/// `None` for UTC
+	pub timezone: Option<jiff::tz::TimeZone>,
+	pub panic_hook: bool,
+	pub redaction: Redaction,
+	/// Extra files, each for the events of one target. See [`route`](Self::route).
+	pub routes: Vec<Route>,
+	/// `None` to not time spans. See [`span_timing`](Self::span_timing).
+	pub span_timing: Option<SpanThresholds>,
+	/// See [`tokio_console`](Self::tokio_console).
+	#[cfg(feature = "tokio-console")]
+	pub tokio_console: bool,
+	/// See [`service_name`](Self::service_name).
+	pub service_name: Option<String>,
+	/// `None` still exports when `OTEL_EXPORTER_OTLP_ENDPOINT` is set, configured purely from the `OTEL_*` env.
+	#[cfg(feature = "otlp")]
+	pub otlp: Option<OtlpConfig>,
+}
+
+impl Default for LogDestination {
+	fn default() -> Self {
+		Self {
+			kind: LogDestinationKind::default(),
+			stderr_errors: false,
+			compiled_directives: None,
+			rotation: RotationPolicy::default(),
+			format: LogFormat::default(),
+			stderr_format: LogFormat::Text,
+			timezone: None,
+			panic_hook: false,
+			redaction: Redaction::default(),
+			routes: Vec::new(),
+			span_timing: None,
+			#[cfg(feature = "tokio-console")]
+			tokio_console: false,
+			service_name: None,
+			#[cfg(feature = "otlp")]
+			otlp: None,
+		}
+	}
+}
+
+#[derive(Clone, Debug, Default)]
+pub enum LogDestinationKind {
+	#[default]
+	Stdout,
+	File {
+		path: PathBuf,
+	},
+	#[cfg(all(not(target_arch = "wasm32"), feature = "xdg"))]
+	Xdg {
+		dname: String,
+		fname: Option<String>,
+	},
+}
+/// Where [`LogDestination::xdg`] logs for app `dname`: `$XDG_STATE_HOME/{dname}/{fname}.log`.
+/// `None` if there's no state home to speak of (`$HOME` unset).
+#[cfg(all(not(target_arch = "wasm32"), feature = "xdg"))]
+pub fn xdg_log_path(dname: &str, fname: Option<&str>) -> Option<PathBuf> {
+	let state_home = xdg::BaseDirectories::with_prefix(dname).get_state_home()?;
+	Some(state_home.join(xdg_log_filename(fname)))
+}
+
+#[cfg(all(not(target_arch = "wasm32"), feature = "xdg"))]
+fn xdg_log_filename(fname: Option<&str>) -> String {
+	fname
+		.map(|s| if s.ends_with(".log") { s.to_string() } else { format!("{s}.log") })
+		.unwrap_or_else(|| ".log".to_string())
+}
+
+impl From<&str> for LogDestination {
+	fn from(s: &str) -> Self {
+		if s == "stdout" { LogDestination::default() } else { LogDestination::file(s) }
+	}
+}
+
+impl From<PathBuf> for LogDestination {
+	fn from(path: PathBuf) -> Self {
+		LogDestination::file(path)
+	}
+}
+
+pub(crate) fn normalize_directives(s: &str) -> String {
+	s.lines().map(|l| l.trim()).filter(|l| !l.is_empty() && !l.starts_with('#')).collect::<Vec<_>>().join(",")
+}
+
+/// Picks the directives to run with, along with a description of where they came from (`None` for the defaults).
+fn resolve_directives(log_dir_path: Option<&Path>, compiled_directives: Option<&'static str>) -> (Cow<'static, str>, Option<String>) {
+	// Priority order:
+	// 1. .cargo/log_directives file (for development - highest priority)
+	// 2. _log_directives in log directory (for runtime override of installed binaries)
+	// 3. Compiled-in directives (production defaults, embedded via build.rs)
+	// 4. Hard-coded default directives
+
+	if let Ok(s) = std::fs::read_to_string(CARGO_DIRECTIVES_PATH) {
+		(Cow::Owned(normalize_directives(&s)), Some(CARGO_DIRECTIVES_PATH.to_owned()))
+	} else if let Some(s) = log_dir_path.and_then(|p| std::fs::read_to_string(p).ok()) {
+		(Cow::Owned(normalize_directives(&s)), log_dir_path.map(|p| p.display().to_string()))
+	} else if let Some(compiled) = compiled_directives {
+		(Cow::Owned(normalize_directives(compiled)), Some("compiled-in (LOG_DIRECTIVES)".to_owned()))
+	} else {
+		(Cow::Borrowed(DEFAULT_DIRECTIVES), None)
+	}
+}
+
+fn filter_with_directives(logs_during_init: &mut Vec<Box<dyn FnOnce()>>, log_dir: Option<&Path>, compiled_directives: Option<&'static str>) -> EnvFilter {
+	let log_dir_path = log_dir.map(|d| d.join(DIRECTIVES_FILENAME));
+	let (directives, source) = resolve_directives(log_dir_path.as_deref(), compiled_directives);
+
+	match source {
+		Some(path) => {
+			let directives_str = directives.clone().into_owned();
+			logs_during_init.push(Box::new(move || info!("Using log directives from `{path}`:\n{directives_str}")));
+		}
+		None => {
+			let cargo_path = CARGO_DIRECTIVES_PATH.to_owned();
+			let log_dir_msg = log_dir_path.map(|p| p.display().to_string());
+			logs_during_init.push(Box::new(move || match log_dir_msg {
+				Some(p) => warn!("No log directives file found (checked `{cargo_path}` and `{p}`), using defaults"),
+				None => warn!("No log directives file found at `{cargo_path}`, using defaults"),
+			}));
+		}
+	}
+
+	match EnvFilter::builder().parse(console_directives(&directives)) {
+		Ok(filter) => filter,
+		Err(e) => {
+			let directives = directives.into_owned();
+			logs_during_init.push(Box::new(move || {
+				error!("Error parsing tracing directives, falling back to `{DEFAULT_DIRECTIVES}`:\n```\n{directives}\n```\n{e}")
+			}));
+			EnvFilter::new(console_directives(DEFAULT_DIRECTIVES))
+		}
+	}
+}
+
+/// `directives`, plus what tokio-console needs if it's running. It never does in the browser.
+fn console_directives(directives: &str) -> Cow<'_, str> {
+	#[cfg(native)]
+	return tokio_console::directives(directives);
+	#[cfg(not(native))]
+	Cow::Borrowed(directives)
+}
+
+#[cfg(native)]
+fn trace_the_init() {
+	let args: Vec<_> = args_os().collect();
+	let vars: BTreeMap<_, _> = vars_os().collect();
+	tracing::trace!("Executed as {exe:?} in {dir:?}\n", exe = current_exe(), dir = current_dir(),);
+	tracing::trace!("Arguments: {args:#?}\n", args = args);
+	// Its size is capped by [`Redaction::truncate`]; secret-looking values are masked with [`Redaction::env_heuristics`].
+	tracing::trace!("Environment: {vars:#?}\n", vars = vars);
+}
+
+/// Installs `color_eyre` with frame filters that strip async runtime noise
+/// (tokio, mio, futures_util) and test harness / panic machinery from backtraces.
+///
+/// Compiled symbols embed crate disambiguator hashes (e.g. `std[d04b43a2428f6e7c]::panicking`),
+/// so we strip `[...]` before matching.
+#[macro_export]
+macro_rules! install_color_eyre {
+	() => {
+		color_eyre::config::HookBuilder::default()
+			.add_frame_filter(Box::new(|frames| {
+				let strip_hashes = |name: &str| -> String {
+					let mut out = String::with_capacity(name.len());
+					let mut chars = name.chars();
+					while let Some(c) = chars.next() {
+						if c == '[' {
+							for c2 in chars.by_ref() {
+								if c2 == ']' {
+									break;
+								}
+							}
+						} else {
+							out.push(c);
+						}
+					}
+					out
+				};
+				frames.retain(|frame| {
+					let Some(name) = frame.name.as_ref() else { return true };
+					let name = strip_hashes(name);
+					let name = name.as_str();
+					// async runtime
+					if name.starts_with("tokio::") || name.starts_with("<tokio::") {
+						return false;
+					}
+					if name.starts_with("mio::") {
+						return false;
+					}
+					if name.starts_with("futures_util::") {
+						return false;
+					}
+					if name.contains("::future::") {
+						return false;
+					}
+					if name.contains("core::pin::Pin") {
+						return false;
+					}
+					// test harness & panic machinery below user code
+					if name.starts_with("test::") {
+						return false;
+					}
+					if name.contains("std::panicking") || name.contains("std::panic::") {
+						return false;
+					}
+					if name.contains("std::thread") || name.contains("std::sys::") {
+						return false;
+					}
+					if name.contains("core::ops::function") {
+						return false;
+					}
+					if name.contains("core::panic::unwind_safe") {
+						return false;
+					}
+					if name.starts_with("<alloc::boxed::Box<dyn core") {
+						return false;
+					}
+					if name.starts_with("__rustc") {
+						return false;
+					}
+					if name.contains("start_thread") || name.contains("clone3") {
+						return false;
+					}
+					true
+				});
+			}))
+			.install()
+			.unwrap()
+	};
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::{Arc, Mutex};
+
+	use tracing_subscriber::{EnvFilter, fmt::MakeWriter};
+
+	use super::*;
+
+	/// What the log layers under test write, kept in memory. Clones share the buffer.
+	#[derive(Clone, Default)]
+	pub(super) struct TestSink(Arc<Mutex<Vec<u8>>>);
+
+	impl TestSink {
+		/// Everything written so far.
+		pub(super) fn output(&self) -> String {
+			String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+		}
+	}
+
+	impl std::io::Write for TestSink {
+		fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+			self.0.lock().unwrap().extend_from_slice(buf);
+			Ok(buf.len())
+		}
+
+		fn flush(&mut self) -> std::io::Result<()> {
+			Ok(())
+		}
+	}
+
+	impl<'a> MakeWriter<'a> for TestSink {
+		type Writer = Self;
+
+		fn make_writer(&'a self) -> Self::Writer {
+			self.clone()
+		}
+	}
+
+	#[test]
+	fn normalize_directives_handles_mixed_formats() {
+		let input = r#"
+
+debug,hyper=info,hyper_util=info
+# this is a comment
+warn
+  trace
+my_crate=debug
+
+"#;
+		let normalized = normalize_directives(input);
+		assert_eq!(normalized, "debug,hyper=info,hyper_util=info,warn,trace,my_crate=debug");
+
+		// Verify it actually parses
+		EnvFilter::builder().parse(&normalized).expect("normalized directives should parse");
+	}
+}
@@ -0,0 +1,168 @@
+//! What a [`LogDestination`](super::LogDestination) can be configured with, on native and in the browser alike. What
+//! acts on it lives in the native-only modules.
+
+use std::{
+	path::{Path, PathBuf},
+	str::FromStr,
+	time::Duration,
+};
+
+use crate::primitives::Timeframe;
+
+/// Default [`Rotation::MaxSize`] threshold (20GB)
+const LOG_MAX_SIZE_BYTES: u64 = 20 * 1024 * 1024 * 1024;
+
+/// How a log layer renders events. Set through [`LogDestination::format`](super::LogDestination::format) for the log
+/// file (or stdout), and [`LogDestination::stderr_format`](super::LogDestination::stderr_format) for the stderr layer.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, strum::Display, strum::EnumString)]
+#[strum(serialize_all = "kebab-case")]
+pub enum LogFormat {
+	/// A multi-line block per event, with source location and span stack on lines of their own. What the log file
+	/// has always held (`fmt::layer().json().pretty()`, of which `pretty` wins).
+	#[default]
+	PrettyJson,
+	/// One JSON object per line, with `timestamp`, `level`, `target`, `fields`, `filename`, `line_number`, the
+	/// current `span` and the full `spans` stack. For `jq -c`, `grep` and log shippers.
+	JsonLines,
+	/// [`JsonLines`](Self::JsonLines)' objects, indented over several lines each. Still read by `v_utils-logs`, which
+	/// takes a log for a stream of concatenated JSON values.
+	IndentedJson,
+	/// One human-readable line per event, prefixed by the spans it was emitted in.
+	Text,
+	/// [`Text`](Self::Text), abbreviated.
+	Compact,
+}
+
+/// When the live log segment is closed and a fresh one started.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Rotation {
+	/// Once the live segment would grow past this many bytes.
+	MaxSize(u64),
+	/// At every UTC midnight.
+	Daily,
+	/// At the top of every UTC hour.
+	Hourly,
+}
+
+impl Default for Rotation {
+	/// [`Rotation::MaxSize`] of 20GB
+	fn default() -> Self {
+		Self::MaxSize(LOG_MAX_SIZE_BYTES)
+	}
+}
+
+/// How the log file is rotated and how many closed segments are retained.
+/// Set through [`LogDestination::rotate`](super::LogDestination::rotate), [`keep`](super::LogDestination::keep) and [`gzip`](super::LogDestination::gzip).
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct RotationPolicy {
+	/// `None` (the default) writes the configured path itself, and truncates it where [`Rotation::default`] would rotate.
+	/// `keep` and `gzip` only apply with a rotation.
+	pub rotation: Option<Rotation>,
+	/// Closed segments retained besides the live one. `0` (the default) retains none: each rotation deletes the segment it
+	/// closes, and since every process start opens a new segment, so does a restart. It does not mean "unlimited".
+	pub keep: usize,
+	/// Gzip closed segments into `{segment}.gz`.
+	pub gzip: bool,
+}
+
+/// Where the live segment of a log configured at `path` with a [`Rotation`] can be read: `{stem}.current.log` next to it, or
+/// `current.log` for the default `.log`. A symlink to the live segment, except on non-unix, where it is the live segment.
+pub fn current_log_path(path: &Path) -> PathBuf {
+	let dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+	match stem_of(path).as_str() {
+		"" => dir.join("current.log"),
+		stem => dir.join(format!("{stem}.current.log")),
+	}
+}
+
+/// File name without its `.log` extension; empty for the default `.log`.
+pub(super) fn stem_of(path: &Path) -> String {
+	let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+	name.strip_suffix(".log").unwrap_or(&name).to_owned()
+}
+
+/// A log file for the events of `target` and its submodules. See [`LogDestination::route`](super::LogDestination::route).
+#[derive(Clone, Debug)]
+pub struct Route {
+	/// Module path, e.g. `bot::orders`
+	pub target: String,
+	/// Relative paths are resolved against the main log's directory (the working directory when logging to stdout)
+	pub path: PathBuf,
+	/// Narrow what this file takes, within what the main directives enable (default: all of it)
+	pub directives: Option<String>,
+	/// Default: the main log's
+	pub format: Option<LogFormat>,
+	/// Default: the main log's
+	pub rotation: Option<RotationPolicy>,
+}
+
+impl Route {
+	pub fn new<T: Into<String>, P: Into<PathBuf>>(target: T, path: P) -> Self {
+		Self {
+			target: target.into(),
+			path: path.into(),
+			directives: None,
+			format: None,
+			rotation: None,
+		}
+	}
+
+	pub fn directives<S: Into<String>>(mut self, directives: S) -> Self {
+		self.directives = Some(directives.into());
+		self
+	}
+
+	pub fn format(mut self, format: LogFormat) -> Self {
+		self.format = Some(format);
+		self
+	}
+
+	pub fn rotation(mut self, rotation: RotationPolicy) -> Self {
+		self.rotation = Some(rotation);
+		self
+	}
+}
+
+/// Per-target thresholds for [`LogDestination::span_timing`](super::LogDestination::span_timing), in directives syntax:
+/// `500ms,bot::orders=50ms,bot::ws=5s`. A bare duration applies to every target; otherwise the most specific target
+/// wins. Durations are [`Timeframe`]s, with the unit spelled out. The `_span_thresholds` files take the same syntax, one
+/// entry per line if you like, with `#` comments.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SpanThresholds {
+	pub(super) default: Option<Duration>,
+	/// Most specific first.
+	pub(super) by_target: Vec<(String, Duration)>,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("invalid span threshold `{entry}`: {reason}")]
+pub struct SpanThresholdsError {
+	entry: String,
+	reason: String,
+}
+
+impl FromStr for SpanThresholds {
+	type Err = SpanThresholdsError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let mut thresholds = Self::default();
+		for entry in super::normalize_directives(s).split(',').filter(|e| !e.is_empty()) {
+			let err = |reason: String| SpanThresholdsError { entry: entry.to_owned(), reason };
+			let (target, duration) = match entry.split_once('=') {
+				Some((target, duration)) => (Some(target.trim()), duration.trim()),
+				None => (None, entry.trim()),
+			};
+			// A bare number would be minutes to `Timeframe`, which nobody means here.
+			if !duration.ends_with(|c: char| c.is_ascii_alphabetic()) {
+				return Err(err("expected a duration with a unit, e.g. `250ms` or `2s`".to_owned()));
+			}
+			let duration = Timeframe::from_str(duration).map_err(|e| err(e.to_string()))?.duration();
+			match target {
+				Some(target) => thresholds.by_target.push((target.to_owned(), duration)),
+				None => thresholds.default = Some(duration),
+			}
+		}
+		thresholds.by_target.sort_by_key(|(target, _)| std::cmp::Reverse(target.len()));
+		Ok(thresholds)
+	}
+}
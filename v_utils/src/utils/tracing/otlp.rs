@@ -0,0 +1,247 @@
+//! OTLP export of logs and traces over HTTP, alongside the file/stderr layers.
+//!
+//! Active when configured through [`LogDestination::otlp`](super::LogDestination::otlp), or when
+//! `OTEL_EXPORTER_OTLP_ENDPOINT` is set, so non-cluster runs stay untouched. HTTP (reqwest-blocking) is deliberate:
+//! init runs before any tokio runtime exists, and the gRPC exporter would panic for lack of a reactor.
+
+#[cfg(feature = "otlp")]
+use std::{
+	borrow::Cow,
+	collections::{BTreeMap, HashMap},
+	sync::OnceLock,
+};
+
+/// Programmatic OTLP setup. Every `None` defers to the standard `OTEL_*` env.
+#[cfg(feature = "otlp")]
+#[derive(Clone, Debug, Default)]
+pub struct OtlpConfig {
+	/// Collector base URL, e.g. `http://localhost:4318`; `/v1/traces` and `/v1/logs` are appended, as for `OTEL_EXPORTER_OTLP_ENDPOINT`.
+	pub endpoint: Option<String>,
+	/// `service.name`. Without it nor `OTEL_SERVICE_NAME`, [`LogDestination::service_name`](super::LogDestination::service_name),
+	/// and failing that the executable's name.
+	pub service_name: Option<String>,
+	/// Extra resource attributes, on top of `OTEL_RESOURCE_ATTRIBUTES`.
+	pub resource_attrs: BTreeMap<String, String>,
+	/// Fraction of root traces to sample, in `0.0..=1.0`. Child spans follow their parent's decision.
+	pub sample_ratio: Option<f64>,
+	/// Sent with every export request, e.g. for auth.
+	pub headers: HashMap<String, String>,
+}
+
+#[cfg(feature = "otlp")]
+static OTLP_PROVIDERS: OnceLock<(opentelemetry_sdk::trace::SdkTracerProvider, opentelemetry_sdk::logs::SdkLoggerProvider)> = OnceLock::new();
+
+#[cfg(feature = "otlp")]
+pub(super) fn layer<S>(destination: &super::LogDestination, logs_during_init: &mut Vec<Box<dyn FnOnce()>>) -> Option<Box<dyn tracing_subscriber::Layer<S> + Send + Sync>>
+where
+	S: tracing::Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a> + Send + Sync, {
+	use opentelemetry::{KeyValue, trace::TracerProvider as _};
+	use opentelemetry_otlp::{LogExporter, SpanExporter, WithExportConfig as _, WithHttpConfig as _};
+	use opentelemetry_sdk::{
+		Resource,
+		logs::SdkLoggerProvider,
+		trace::{Sampler, SdkTracerProvider},
+	};
+	use tracing_subscriber::Layer as _;
+
+	let config = match &destination.otlp {
+		Some(config) => Cow::Borrowed(config),
+		None if std::env::var_os("OTEL_EXPORTER_OTLP_ENDPOINT").is_some() => Cow::Owned(OtlpConfig::default()),
+		None => return None,
+	};
+	let service_name = service_name(&config, destination);
+	// The builder also picks up OTEL_RESOURCE_ATTRIBUTES.
+	let resource = Resource::builder()
+		.with_service_name(service_name.clone())
+		.with_attributes(config.resource_attrs.iter().map(|(k, v)| KeyValue::new(k.clone(), v.clone())))
+		.build();
+
+	let mut span_exporter = SpanExporter::builder().with_http();
+	let mut log_exporter = LogExporter::builder().with_http();
+	if let Some(endpoint) = &config.endpoint {
+		span_exporter = span_exporter.with_endpoint(signal_url(endpoint, "traces"));
+		log_exporter = log_exporter.with_endpoint(signal_url(endpoint, "logs"));
+	}
+	if !config.headers.is_empty() {
+		span_exporter = span_exporter.with_headers(config.headers.clone());
+		log_exporter = log_exporter.with_headers(config.headers.clone());
+	}
+
+	let span_exporter = match span_exporter.build() {
+		Ok(exporter) => exporter,
+		Err(e) => {
+			logs_during_init.push(Box::new(move || tracing::error!("Couldn't build the OTLP span exporter, not exporting: {e}")));
+			return None;
+		}
+	};
+	let log_exporter = match log_exporter.build() {
+		Ok(exporter) => exporter,
+		Err(e) => {
+			logs_during_init.push(Box::new(move || tracing::error!("Couldn't build the OTLP log exporter, not exporting: {e}")));
+			return None;
+		}
+	};
+
+	let mut tracer_provider = SdkTracerProvider::builder().with_batch_exporter(span_exporter).with_resource(resource.clone());
+	if let Some(ratio) = config.sample_ratio {
+		tracer_provider = tracer_provider.with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(ratio))));
+	}
+	let tracer_provider = tracer_provider.build();
+	let traces_layer = tracing_opentelemetry::layer().with_tracer(tracer_provider.tracer(service_name));
+
+	let logger_provider = SdkLoggerProvider::builder().with_batch_exporter(log_exporter).with_resource(resource).build();
+	let logs_layer = opentelemetry_appender_tracing::layer::OpenTelemetryTracingBridge::new(&logger_provider);
+	// Providers own the batch-export threads; keep them alive for the process.
+	let _ = OTLP_PROVIDERS.set((tracer_provider, logger_provider));
+	Some(traces_layer.and_then(logs_layer).boxed())
+}
+
+#[cfg(native)]
+#[cfg(not(feature = "otlp"))]
+pub(super) fn layer<S>(_destination: &super::LogDestination, _logs_during_init: &mut Vec<Box<dyn FnOnce()>>) -> Option<Box<dyn tracing_subscriber::Layer<S> + Send + Sync>>
+where
+	S: tracing::Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a> + Send + Sync, {
+	None
+}
+
+#[cfg(feature = "otlp")]
+fn signal_url(endpoint: &str, signal: &str) -> String {
+	format!("{}/v1/{signal}", endpoint.trim_end_matches('/'))
+}
+
+#[cfg(feature = "otlp")]
+fn service_name(config: &OtlpConfig, destination: &super::LogDestination) -> String {
+	config
+		.service_name
+		.clone()
+		.or_else(|| std::env::var("OTEL_SERVICE_NAME").ok())
+		.or_else(|| destination.service_name.clone())
+		.unwrap_or_else(executable_name)
+}
+
+#[cfg(feature = "otlp")]
+fn executable_name() -> String {
+	std::env::current_exe()
+		.ok()
+		.and_then(|exe| exe.file_stem().map(|s| s.to_string_lossy().into_owned()))
+		.unwrap_or_else(|| "unknown_service".to_owned())
+}
+
+/// Exports whatever is still batched, and stops the export threads. See [`shutdown_guard`](super::shutdown_guard).
+#[cfg(feature = "otlp")]
+pub(super) fn shutdown() {
+	if let Some((tracer_provider, logger_provider)) = OTLP_PROVIDERS.get() {
+		if let Err(e) = tracer_provider.shutdown() {
+			eprintln!("[otlp] Failed to flush spans on shutdown: {e}");
+		}
+		if let Err(e) = logger_provider.shutdown() {
+			eprintln!("[otlp] Failed to flush logs on shutdown: {e}");
+		}
+	}
+}
+
+#[cfg(not(feature = "otlp"))]
+pub(super) fn shutdown() {}
+
+#[cfg(all(test, feature = "otlp"))]
+mod tests {
+	use std::{
+		io::{BufRead as _, BufReader, Read as _, Write as _},
+		net::{TcpListener, TcpStream},
+		sync::{Arc, Mutex},
+		thread,
+	};
+
+	use tracing_subscriber::layer::SubscriberExt as _;
+
+	use super::*;
+	use crate::utils::LogDestination;
+
+	struct Request {
+		path: String,
+		headers: HashMap<String, String>,
+		body: Vec<u8>,
+	}
+
+	/// Accepts OTLP/HTTP exports on a local port, answering each with an empty 200.
+	fn mock_collector() -> (String, Arc<Mutex<Vec<Request>>>) {
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let endpoint = format!("http://{}", listener.local_addr().unwrap());
+		let requests = Arc::new(Mutex::new(Vec::new()));
+		let received = Arc::clone(&requests);
+		thread::spawn(move || {
+			for stream in listener.incoming().flatten() {
+				let received = Arc::clone(&received);
+				thread::spawn(move || serve(stream, &received));
+			}
+		});
+		(endpoint, requests)
+	}
+
+	fn serve(mut stream: TcpStream, received: &Mutex<Vec<Request>>) {
+		let mut reader = BufReader::new(stream.try_clone().unwrap());
+		loop {
+			let mut request_line = String::new();
+			if reader.read_line(&mut request_line).unwrap_or(0) == 0 {
+				return;
+			}
+			let path = request_line.split_whitespace().nth(1).unwrap_or_default().to_owned();
+			let mut headers = HashMap::new();
+			loop {
+				let mut line = String::new();
+				reader.read_line(&mut line).unwrap();
+				match line.trim_end().split_once(':') {
+					Some((name, value)) => _ = headers.insert(name.to_ascii_lowercase(), value.trim().to_owned()),
+					None => break,
+				}
+			}
+			let len = headers.get("content-length").and_then(|l| l.parse().ok()).unwrap_or(0);
+			let mut body = vec![0; len];
+			reader.read_exact(&mut body).unwrap();
+			received.lock().unwrap().push(Request { path, headers, body });
+			stream.write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n").unwrap();
+		}
+	}
+
+	#[test]
+	fn service_name_prefers_config_then_env_then_destination() {
+		let destination = LogDestination::default().service_name("orders-bot");
+		let config = OtlpConfig {
+			service_name: Some("configured".to_owned()),
+			..Default::default()
+		};
+		assert_eq!(service_name(&config, &destination), "configured");
+		if std::env::var_os("OTEL_SERVICE_NAME").is_none() {
+			assert_eq!(service_name(&OtlpConfig::default(), &destination), "orders-bot");
+		}
+	}
+
+	#[test]
+	fn exports_to_configured_collector_and_flushes_on_shutdown() {
+		let (endpoint, requests) = mock_collector();
+		let destination = LogDestination::default().otlp(OtlpConfig {
+			endpoint: Some(endpoint),
+			service_name: Some("orders-bot".to_owned()),
+			resource_attrs: BTreeMap::from([("deployment.environment".to_owned(), "staging".to_owned())]),
+			headers: HashMap::from([("x-api-key".to_owned(), "hunter2".to_owned())]),
+			..Default::default()
+		});
+		let layer = layer(&destination, &mut Vec::new()).expect("a configured OtlpConfig activates export");
+
+		tracing::subscriber::with_default(tracing_subscriber::registry().with(layer), || {
+			tracing::info_span!("fill").in_scope(|| tracing::info!("filled"));
+		});
+		// Batches are only sent on their interval, or on shutdown.
+		shutdown();
+
+		let requests = requests.lock().unwrap();
+		let mut paths: Vec<&str> = requests.iter().map(|r| r.path.as_str()).collect();
+		paths.sort_unstable();
+		assert_eq!(paths, ["/v1/logs", "/v1/traces"]);
+		for request in requests.iter() {
+			assert_eq!(request.headers.get("x-api-key").map(String::as_str), Some("hunter2"));
+			let body = String::from_utf8_lossy(&request.body);
+			assert!(body.contains("orders-bot") && body.contains("staging"), "resource missing from {}", request.path);
+		}
+	}
+}
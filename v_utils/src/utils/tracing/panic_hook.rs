@@ -0,0 +1,41 @@
+//! Panics as log events, so the ones in background threads make it into the log file rather than only stderr.
+
+use std::{backtrace::Backtrace, panic::PanicHookInfo};
+
+use tracing_error::SpanTrace;
+
+/// Target of the recorded panics. Kept off the stderr layer, where the chained hook prints the panic already.
+pub(super) const TARGET: &str = "panic";
+
+/// Chains onto whatever hook is installed (e.g. `color_eyre`'s), which still runs after the event is emitted.
+pub(super) fn install() {
+	let previous = std::panic::take_hook();
+	std::panic::set_hook(Box::new(move |info| {
+		record(info);
+		previous(info);
+	}));
+}
+
+fn record(info: &PanicHookInfo<'_>) {
+	let message = info
+		.payload()
+		.downcast_ref::<&str>()
+		.copied()
+		.or_else(|| info.payload().downcast_ref::<String>().map(String::as_str))
+		.unwrap_or("Box<dyn Any>");
+	let location = info.location().map(|l| l.to_string()).unwrap_or_default();
+	let thread = std::thread::current();
+	let thread = thread.name().unwrap_or("<unnamed>");
+	// Still on the panicking thread, so both see the stack and span context of the panic site.
+	let backtrace = Backtrace::force_capture();
+	let span_trace = SpanTrace::capture();
+	tracing::error!(
+		target: TARGET,
+		location = %location,
+		thread = thread,
+		backtrace = %backtrace,
+		span_trace = %span_trace,
+		"{message}"
+	);
+}
+
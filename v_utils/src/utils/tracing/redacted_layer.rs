@@ -0,0 +1,244 @@
+//! [`Redactor::wrap`], for the layers that take events as values rather than text (OTLP): they are handed copies with
+//! the field values already redacted.
+
+use std::{any::TypeId, fmt, sync::Arc};
+
+use tracing::{
+	Event, Metadata, Subscriber,
+	field::{Field, FieldSet, Value, Visit},
+	span,
+	subscriber::Interest,
+};
+use tracing_subscriber::{Layer, filter::LevelFilter, layer::Context, registry::LookupSpan};
+
+use super::Redactor;
+
+impl Redactor {
+	/// Hands `inner` copies of events and span fields with their values redacted.
+	pub(super) fn wrap<L>(self: &Arc<Self>, inner: L) -> RedactedLayer<L> {
+		RedactedLayer { inner, redactor: Arc::clone(self) }
+	}
+
+	/// Field `name`'s own `value`, masked and truncated.
+	fn redact_field(&self, name: &str, value: &str) -> String {
+		let mut out = value.to_owned();
+		for (from, to) in self.for_field(name, value).iter().chain(&self.replacements) {
+			if out.contains(from.as_str()) {
+				out = out.replace(from.as_str(), to);
+			}
+		}
+		out
+	}
+
+	/// Redacted copies of the values `record` visits, indexed like `fields`, for building a [`ValueSet`](tracing::field::ValueSet) anew.
+	fn redacted_values(&self, fields: &FieldSet, record: impl FnOnce(&mut dyn Visit)) -> Vec<Option<Box<dyn Value>>> {
+		let mut visitor = RedactingVisitor {
+			redactor: self,
+			values: std::iter::repeat_with(|| None).take(fields.len()).collect(),
+		};
+		record(&mut visitor);
+		visitor.values
+	}
+}
+
+/// Keeps numbers and bools as they are; strings and everything `Debug`-formatted go through [`Redactor::redact_field`].
+struct RedactingVisitor<'a> {
+	redactor: &'a Redactor,
+	values: Vec<Option<Box<dyn Value>>>,
+}
+
+impl RedactingVisitor<'_> {
+	fn set(&mut self, field: &Field, value: impl Value + 'static) {
+		if let Some(slot) = self.values.get_mut(field.index()) {
+			*slot = Some(Box::new(value));
+		}
+	}
+}
+
+impl Visit for RedactingVisitor<'_> {
+	fn record_i64(&mut self, field: &Field, value: i64) {
+		self.set(field, value);
+	}
+
+	fn record_u64(&mut self, field: &Field, value: u64) {
+		self.set(field, value);
+	}
+
+	fn record_i128(&mut self, field: &Field, value: i128) {
+		self.set(field, value);
+	}
+
+	fn record_u128(&mut self, field: &Field, value: u128) {
+		self.set(field, value);
+	}
+
+	fn record_f64(&mut self, field: &Field, value: f64) {
+		self.set(field, value);
+	}
+
+	fn record_bool(&mut self, field: &Field, value: bool) {
+		self.set(field, value);
+	}
+
+	fn record_str(&mut self, field: &Field, value: &str) {
+		self.set(field, self.redactor.redact_field(field.name(), value));
+	}
+
+	fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+		self.set(field, self.redactor.redact_field(field.name(), &format!("{value:?}")));
+	}
+}
+
+fn as_values(values: &[Option<Box<dyn Value>>]) -> Vec<Option<&dyn Value>> {
+	values.iter().map(Option::as_deref).collect()
+}
+
+/// See [`Redactor::wrap`]. Independent of [`RedactionLayer`], so it can sit anywhere in the chain.
+pub(super) struct RedactedLayer<L> {
+	inner: L,
+	redactor: Arc<Redactor>,
+}
+
+impl<L, S> Layer<S> for RedactedLayer<L>
+where
+	L: Layer<S>,
+	S: Subscriber + for<'a> LookupSpan<'a>,
+{
+	fn on_register_dispatch(&self, subscriber: &tracing::Dispatch) {
+		self.inner.on_register_dispatch(subscriber);
+	}
+
+	fn on_layer(&mut self, subscriber: &mut S) {
+		self.inner.on_layer(subscriber);
+	}
+
+	fn register_callsite(&self, metadata: &'static Metadata<'static>) -> Interest {
+		self.inner.register_callsite(metadata)
+	}
+
+	fn enabled(&self, metadata: &Metadata<'_>, ctx: Context<'_, S>) -> bool {
+		self.inner.enabled(metadata, ctx)
+	}
+
+	fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+		let meta = attrs.metadata();
+		let values = self.redactor.redacted_values(meta.fields(), |v| attrs.record(v));
+		let values = as_values(&values);
+		let values = meta.fields().value_set_all(&values);
+		let redacted = match (attrs.is_contextual(), attrs.parent()) {
+			(true, _) => span::Attributes::new(meta, &values),
+			(false, Some(parent)) => span::Attributes::child_of(parent.clone(), meta, &values),
+			(false, None) => span::Attributes::new_root(meta, &values),
+		};
+		self.inner.on_new_span(&redacted, id, ctx);
+	}
+
+	fn max_level_hint(&self) -> Option<LevelFilter> {
+		self.inner.max_level_hint()
+	}
+
+	fn on_record(&self, id: &span::Id, values: &span::Record<'_>, ctx: Context<'_, S>) {
+		let Some(meta) = ctx.metadata(id) else { return };
+		let redacted = self.redactor.redacted_values(meta.fields(), |v| values.record(v));
+		let redacted = as_values(&redacted);
+		self.inner.on_record(id, &span::Record::new(&meta.fields().value_set_all(&redacted)), ctx);
+	}
+
+	fn on_follows_from(&self, id: &span::Id, follows: &span::Id, ctx: Context<'_, S>) {
+		self.inner.on_follows_from(id, follows, ctx);
+	}
+
+	fn event_enabled(&self, event: &Event<'_>, ctx: Context<'_, S>) -> bool {
+		self.inner.event_enabled(event, ctx)
+	}
+
+	fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+		let meta = event.metadata();
+		let values = self.redactor.redacted_values(meta.fields(), |v| event.record(v));
+		let values = as_values(&values);
+		let values = meta.fields().value_set_all(&values);
+		let redacted = match event.is_contextual() {
+			true => Event::new(meta, &values),
+			false => Event::new_child_of(event.parent().cloned(), meta, &values),
+		};
+		self.inner.on_event(&redacted, ctx);
+	}
+
+	fn on_enter(&self, id: &span::Id, ctx: Context<'_, S>) {
+		self.inner.on_enter(id, ctx);
+	}
+
+	fn on_exit(&self, id: &span::Id, ctx: Context<'_, S>) {
+		self.inner.on_exit(id, ctx);
+	}
+
+	fn on_close(&self, id: span::Id, ctx: Context<'_, S>) {
+		self.inner.on_close(id, ctx);
+	}
+
+	fn on_id_change(&self, old: &span::Id, new: &span::Id, ctx: Context<'_, S>) {
+		self.inner.on_id_change(old, new, ctx);
+	}
+
+	unsafe fn downcast_raw(&self, id: TypeId) -> Option<*const ()> {
+		match id == TypeId::of::<Self>() {
+			true => Some(self as *const Self as *const ()),
+			// SAFETY: forwarded as is; `inner` upholds the contract for its own types (e.g. tracing-opentelemetry's `WithContext`).
+			false => unsafe { self.inner.downcast_raw(id) },
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Mutex;
+
+	use tracing_subscriber::layer::SubscriberExt as _;
+
+	use super::*;
+	use crate::utils::tracing::{Redaction, redaction::MASK};
+
+	/// Stands in for the OTLP layers: collects every field value it's handed.
+	struct Collect(Arc<Mutex<Vec<String>>>);
+	impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for Collect {
+		fn on_new_span(&self, attrs: &span::Attributes<'_>, _id: &span::Id, _ctx: Context<'_, S>) {
+			attrs.record(&mut CollectVisitor(&self.0));
+		}
+
+		fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+			event.record(&mut CollectVisitor(&self.0));
+		}
+	}
+	struct CollectVisitor<'a>(&'a Mutex<Vec<String>>);
+	impl Visit for CollectVisitor<'_> {
+		fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+			self.0.lock().unwrap().push(format!("{}={value:?}", field.name()));
+		}
+	}
+
+	#[test]
+	fn wrapped_layers_get_redacted_values() {
+		let config = Redaction {
+			values: vec!["sk-live-42".to_owned()],
+			fields: vec!["api_key".to_owned()],
+			..Default::default()
+		};
+		let seen = Arc::new(Mutex::new(Vec::new()));
+		let subscriber = tracing_subscriber::registry().with(Redactor::new(&config).wrap(Collect(Arc::clone(&seen))));
+		tracing::subscriber::with_default(subscriber, || {
+			tracing::info_span!("venue", api_key = "hunter2-span").in_scope(|| {
+				tracing::info!(api_key = "hunter2", attempt = 3, "connecting with sk-live-42");
+			});
+		});
+		let seen = seen.lock().unwrap();
+		assert_eq!(
+			*seen,
+			[
+				format!("api_key={MASK:?}"),
+				format!("message=\"connecting with {MASK}\""),
+				format!("api_key={MASK:?}"),
+				"attempt=3".to_owned(),
+			]
+		);
+	}
+}
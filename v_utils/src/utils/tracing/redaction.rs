@@ -0,0 +1,358 @@
+//! Masking secrets and capping oversized fields in what the log layers write.
+//!
+//! A layer can't alter an event for the layers after it, so the work is split: [`Redactor::layer`] runs first in the chain and
+//! notes, per event, which rendered values to replace; [`Redactor::writer`] wraps a sink and applies them (along with
+//! the static denylist) to each rendered event. Values are matched raw as well as JSON- and `Debug`-escaped, so every
+//! [`LogFormat`](super::LogFormat) is covered. Layers that take events as values rather than text (OTLP) are wrapped in
+//! [`Redactor::wrap`] instead, which hands them copies with the field values already redacted.
+
+use std::{borrow::Cow, cell::RefCell, ffi::OsString, fmt, io, sync::Arc};
+
+use tracing::{
+	Event, Metadata, Subscriber,
+	field::{Field, Visit},
+	span,
+};
+use tracing_subscriber::{fmt::MakeWriter, layer::Context, registry::LookupSpan};
+
+use crate::utils::truncate_msg;
+
+pub(super) const MASK: &str = "[REDACTED]";
+/// Env vars with any of these in their (uppercased) name are taken for secrets.
+const SECRET_ENV_MARKERS: [&str; 3] = ["KEY", "SECRET", "TOKEN"];
+/// Shorter env values are left alone, so a `TOKEN_LIMIT=1000` doesn't blank out every `1000` in the log.
+const MIN_ENV_SECRET_LEN: usize = 12;
+
+/// What [`LogDestination::redact`](super::LogDestination::redact) masks in the file, stdout and stderr output, and in the
+/// event and span fields exported over OTLP.
+///
+/// Masking in the written output is a find-and-replace over each rendered event, once per form of every secret, so its
+/// cost grows with the number of `values` (and env secrets) times the length of the event. [`fields`](Self::fields) are
+/// cheaper to hit: only the named field's own value is looked for.
+#[derive(Clone, Debug)]
+pub struct Redaction {
+	/// Literal values to mask wherever they appear.
+	pub values: Vec<String>,
+	/// Also mask the values of env vars named like `*KEY*`, `*SECRET*` or `*TOKEN*`, as read at init (default: false).
+	/// Values shorter than 12 characters, and those that parse as a bool or a number, are taken for settings instead.
+	pub env_heuristics: bool,
+	/// Event and span fields whose values are masked, e.g. `api_key`.
+	pub fields: Vec<String>,
+	/// Cut field values past [`truncate_msg`]'s limits, e.g. the env dump at init (default: true).
+	pub truncate: bool,
+}
+
+impl Default for Redaction {
+	fn default() -> Self {
+		Self {
+			values: Vec::new(),
+			env_heuristics: false,
+			fields: Vec::new(),
+			truncate: true,
+		}
+	}
+}
+
+/// [`Redaction`], resolved.
+#[derive(Debug)]
+pub(super) struct Redactor {
+	/// (rendered value, replacement), longest first so a secret containing another is masked whole.
+	pub(super) replacements: Vec<(String, String)>,
+	fields: Vec<String>,
+	truncate: bool,
+}
+
+thread_local! {
+	/// Replacements for the event being rendered on this thread, set by [`RedactionLayer`] ahead of the sinks.
+	static EVENT_REPLACEMENTS: RefCell<Vec<(String, String)>> = const { RefCell::new(Vec::new()) };
+}
+
+impl Redactor {
+	pub(super) fn new(config: &Redaction) -> Arc<Self> {
+		let mut secrets: Vec<String> = config.values.iter().filter(|v| !v.is_empty()).cloned().collect();
+		if config.env_heuristics {
+			secrets.extend(secret_env_values(std::env::vars_os()));
+		}
+		let mut replacements: Vec<(String, String)> = secrets.iter().flat_map(|s| masked(s)).collect();
+		sort_and_dedup(&mut replacements);
+		Arc::new(Self {
+			replacements,
+			fields: config.fields.clone(),
+			truncate: config.truncate,
+		})
+	}
+
+	pub(super) fn layer(self: &Arc<Self>) -> RedactionLayer {
+		RedactionLayer(Arc::clone(self))
+	}
+
+	pub(super) fn writer<M>(self: &Arc<Self>, make_writer: M) -> RedactingMakeWriter<M> {
+		RedactingMakeWriter {
+			inner: make_writer,
+			redactor: Arc::clone(self),
+		}
+	}
+
+	fn apply<'a>(&self, rendered: &'a str) -> Cow<'a, str> {
+		let mut out = Cow::Borrowed(rendered);
+		// Event-specific first: truncations are computed from the unmasked values.
+		EVENT_REPLACEMENTS.with(|event| {
+			for (from, to) in event.borrow().iter().chain(&self.replacements) {
+				if out.contains(from.as_str()) {
+					out = Cow::Owned(out.replace(from.as_str(), to));
+				}
+			}
+		});
+		out
+	}
+
+	/// Replacements owed to the value of field `name`.
+	pub(super) fn for_field(&self, name: &str, value: &str) -> Vec<(String, String)> {
+		if value.is_empty() {
+			return Vec::new();
+		}
+		if self.fields.iter().any(|f| f == name) {
+			return masked(value);
+		}
+		if self.truncate {
+			let truncated = truncate_msg(value);
+			if truncated != value {
+				return forms(value).into_iter().zip(forms(&truncated)).collect();
+			}
+		}
+		Vec::new()
+	}
+}
+
+fn secret_env_values(vars: impl IntoIterator<Item = (OsString, OsString)>) -> Vec<String> {
+	vars.into_iter()
+		.filter(|(name, _)| {
+			let name = name.to_string_lossy().to_uppercase();
+			SECRET_ENV_MARKERS.iter().any(|m| name.contains(m))
+		})
+		.filter_map(|(_, value)| value.into_string().ok())
+		.filter(|value| value.chars().count() >= MIN_ENV_SECRET_LEN && value.parse::<f64>().is_err() && !["true", "false"].iter().any(|b| value.eq_ignore_ascii_case(b)))
+		.collect()
+}
+
+fn masked(secret: &str) -> Vec<(String, String)> {
+	forms(secret).into_iter().map(|f| (f, MASK.to_owned())).collect()
+}
+
+/// How `value` may show up in the output: as is, and escaped in a JSON or `Debug`-formatted string.
+fn forms(value: &str) -> Vec<String> {
+	let json = serde_json::to_string(value).expect("strings serialize");
+	let debug = format!("{value:?}");
+	let mut forms = vec![value.to_owned(), json[1..json.len() - 1].to_owned(), debug[1..debug.len() - 1].to_owned()];
+	forms.dedup();
+	forms
+}
+
+fn sort_and_dedup(replacements: &mut Vec<(String, String)>) {
+	replacements.sort_by(|a, b| b.0.len().cmp(&a.0.len()).then_with(|| a.0.cmp(&b.0)));
+	replacements.dedup_by(|a, b| a.0 == b.0);
+}
+
+/// Must come before the formatting layers, so it has set the replacements by the time they write.
+pub(super) struct RedactionLayer(Arc<Redactor>);
+
+/// Replacements for a span's own fields, which the formatting layers repeat on every event within it.
+struct SpanReplacements(Vec<(String, String)>);
+
+impl<S> tracing_subscriber::Layer<S> for RedactionLayer
+where
+	S: Subscriber + for<'a> LookupSpan<'a>,
+{
+	fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+		let mut visitor = ReplacementVisitor {
+			redactor: &self.0,
+			found: Vec::new(),
+		};
+		attrs.record(&mut visitor);
+		if let Some(span) = ctx.span(id) {
+			span.extensions_mut().insert(SpanReplacements(visitor.found));
+		}
+	}
+
+	fn on_record(&self, id: &span::Id, values: &span::Record<'_>, ctx: Context<'_, S>) {
+		let Some(span) = ctx.span(id) else { return };
+		let mut visitor = ReplacementVisitor {
+			redactor: &self.0,
+			found: Vec::new(),
+		};
+		values.record(&mut visitor);
+		if let Some(SpanReplacements(found)) = span.extensions_mut().get_mut::<SpanReplacements>() {
+			found.extend(visitor.found);
+		}
+	}
+
+	fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+		let mut visitor = ReplacementVisitor {
+			redactor: &self.0,
+			found: Vec::new(),
+		};
+		event.record(&mut visitor);
+		let mut found = visitor.found;
+		if let Some(scope) = ctx.event_scope(event) {
+			for span in scope {
+				if let Some(SpanReplacements(of_span)) = span.extensions().get::<SpanReplacements>() {
+					found.extend(of_span.iter().cloned());
+				}
+			}
+		}
+		sort_and_dedup(&mut found);
+		EVENT_REPLACEMENTS.with(|event| *event.borrow_mut() = found);
+	}
+}
+
+struct ReplacementVisitor<'a> {
+	redactor: &'a Redactor,
+	found: Vec<(String, String)>,
+}
+
+impl Visit for ReplacementVisitor<'_> {
+	fn record_str(&mut self, field: &Field, value: &str) {
+		self.found.extend(self.redactor.for_field(field.name(), value));
+	}
+
+	fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+		self.found.extend(self.redactor.for_field(field.name(), &format!("{value:?}")));
+	}
+}
+
+pub(super) struct RedactingMakeWriter<M> {
+	inner: M,
+	redactor: Arc<Redactor>,
+}
+
+impl<'a, M: MakeWriter<'a>> MakeWriter<'a> for RedactingMakeWriter<M> {
+	type Writer = RedactingWriter<M::Writer>;
+
+	fn make_writer(&'a self) -> Self::Writer {
+		RedactingWriter {
+			inner: self.inner.make_writer(),
+			redactor: Arc::clone(&self.redactor),
+		}
+	}
+
+	fn make_writer_for(&'a self, meta: &Metadata<'_>) -> Self::Writer {
+		RedactingWriter {
+			inner: self.inner.make_writer_for(meta),
+			redactor: Arc::clone(&self.redactor),
+		}
+	}
+}
+
+/// The formatting layers write each rendered event in one go, so a value is never split across writes.
+pub(super) struct RedactingWriter<W> {
+	inner: W,
+	redactor: Arc<Redactor>,
+}
+
+impl<W: io::Write> io::Write for RedactingWriter<W> {
+	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+		match std::str::from_utf8(buf) {
+			Ok(rendered) => self.inner.write_all(self.redactor.apply(rendered).as_bytes())?,
+			Err(_) => self.inner.write_all(buf)?,
+		}
+		Ok(buf.len())
+	}
+
+	fn flush(&mut self) -> io::Result<()> {
+		self.inner.flush()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Mutex;
+
+	use tracing_subscriber::layer::SubscriberExt as _;
+
+	use super::*;
+	use crate::utils::tracing::{LogFormat, format::fmt_layer, tests::TestSink};
+
+	fn render(format: LogFormat, config: &Redaction, log: impl FnOnce()) -> String {
+		let sink = TestSink::default();
+		let redactor = Redactor::new(config);
+		let subscriber = tracing_subscriber::registry()
+			.with(redactor.layer())
+			.with(fmt_layer(format, Default::default(), redactor.writer(sink.clone()), false, false));
+		tracing::subscriber::with_default(subscriber, log);
+		sink.output()
+	}
+
+	#[test]
+	fn masks_denylisted_values_and_named_fields_in_every_format() {
+		let config = Redaction {
+			values: vec!["sk-live-\"42\"".to_owned()],
+			fields: vec!["api_key".to_owned()],
+			..Default::default()
+		};
+		for format in [LogFormat::Text, LogFormat::JsonLines, LogFormat::PrettyJson, LogFormat::IndentedJson, LogFormat::Compact] {
+			let out = render(format, &config, || {
+				tracing::info_span!("venue", api_key = "hunter2-span").in_scope(|| {
+					tracing::info!(api_key = "hunter2", "connecting with sk-live-\"42\"");
+				});
+			});
+			for secret in ["hunter2", "sk-live"] {
+				assert!(!out.contains(secret), "{format}: `{secret}` leaked into:\n{out}");
+			}
+			assert!(out.contains(MASK), "{format}:\n{out}");
+			assert!(out.contains("connecting with"), "{format}:\n{out}");
+		}
+	}
+
+	#[test]
+	fn truncates_oversized_fields() {
+		let huge = (0..200).map(|i| format!("VAR_{i}=…")).collect::<Vec<_>>().join("\n");
+		let out = render(LogFormat::JsonLines, &Redaction::default(), || tracing::trace!(dump = %huge, "Environment"));
+		let event: serde_json::Value = serde_json::from_str(out.trim_end()).unwrap();
+		let dump = event["fields"]["dump"].as_str().unwrap();
+		assert!(dump.contains("// truncated at"), "{dump}");
+		assert!(dump.starts_with("VAR_0=…") && dump.ends_with("VAR_199=…"), "{dump}");
+		assert_eq!(dump.lines().count(), 25 + 1 + 25);
+	}
+
+	/// Notes the level of every event it's asked for a writer for.
+	struct Levels(Arc<Mutex<Vec<tracing::Level>>>);
+	impl<'a> MakeWriter<'a> for Levels {
+		type Writer = io::Sink;
+
+		fn make_writer(&'a self) -> Self::Writer {
+			io::sink()
+		}
+
+		fn make_writer_for(&'a self, meta: &Metadata<'_>) -> Self::Writer {
+			self.0.lock().unwrap().push(*meta.level());
+			io::sink()
+		}
+	}
+
+	#[test]
+	fn writer_forwards_event_metadata() {
+		let redactor = Redactor::new(&Redaction::default());
+		let levels = Arc::new(Mutex::new(Vec::new()));
+		let subscriber = tracing_subscriber::registry()
+			.with(redactor.layer())
+			.with(tracing_subscriber::fmt::layer().with_writer(redactor.writer(Levels(Arc::clone(&levels)))));
+		tracing::subscriber::with_default(subscriber, || tracing::warn!("disk almost full"));
+		assert_eq!(*levels.lock().unwrap(), [tracing::Level::WARN]);
+	}
+
+	#[test]
+	fn env_heuristics_pick_secret_looking_names() {
+		let vars = [
+			("BINANCE_API_KEY", "abcd1234efgh5678"),
+			("GH_TOKEN", "ghp_xyz0123456789"),
+			("TOKEN_LIMIT", "1"),
+			("ORDER_KEY_TTL_MS", "100000000000000"),
+			("DISABLE_TOKEN_CHECK", "true"),
+			("SHORT_KEY", "abcd1234"),
+			("HOME", "/home/v/somewhere/long"),
+			("client_secret", "s3cr3t-s3cr3t"),
+		];
+		let found = secret_env_values(vars.map(|(k, v)| (k.into(), v.into())));
+		assert_eq!(found, ["abcd1234efgh5678", "ghp_xyz0123456789", "s3cr3t-s3cr3t"]);
+	}
+}
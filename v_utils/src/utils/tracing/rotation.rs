@@ -0,0 +1,385 @@
+//! Size- and time-based rotation of the log file.
+//!
+//! Without a [`Rotation`] configured, events go to the configured path itself, which is truncated when opened and again
+//! should it grow past 20GB. With one, events go to a timestamped segment next to the configured path
+//! (`{stem}.{stamp}.log`), and [`current_log_path`] (`{stem}.current.log`, or `current.log` for the default `.log`) is
+//! kept as a symlink to the live segment, so `tail -F` and anything else opening it by name keeps working across
+//! rotations. Without symlinks (non-unix), the live segment is written at that path directly and renamed to its
+//! timestamped name once closed. Rotating only opens the next segment and swaps the symlink under the writer lock;
+//! compressing and pruning closed segments happens on a background thread, so writers never wait on either.
+
+use std::{
+	fs::{File, OpenOptions},
+	io::{self, Write},
+	path::{Path, PathBuf},
+	sync::{Arc, Mutex, mpsc},
+	thread,
+	time::{SystemTime, UNIX_EPOCH},
+};
+
+use super::{Rotation, RotationPolicy, current_log_path, options::stem_of};
+
+/// `jiff` strftime format of the segment timestamp. Lexicographic order of the output is chronological.
+const STAMP_FORMAT: &str = "%Y-%m-%dT%H-%M-%S%.3f";
+/// Shape of [`STAMP_FORMAT`]'s output, `0` standing for any digit.
+const STAMP_SHAPE: &str = "0000-00-00T00-00-00.000";
+
+/// `Write` handle onto the rotating log. Clones share the live segment.
+#[derive(Clone)]
+pub(super) struct RotatingFileWriter {
+	live: Arc<Mutex<LiveSegment>>,
+	rotation: Rotation,
+	/// `None` without a configured rotation: the live segment is then the configured path, truncated instead of rotated.
+	segments: Option<Segments>,
+}
+
+#[derive(Clone)]
+struct Segments {
+	layout: Arc<SegmentLayout>,
+	/// Receives the live segment's start after each rotation; the housekeeper compresses and prunes everything older.
+	housekeeper: mpsc::Sender<i64>,
+}
+
+impl RotatingFileWriter {
+	/// Opens `path` afresh, or with a rotation configured, a fresh segment for it with its [`current_log_path`] pointed at it.
+	///
+	/// When rotating, a regular file left at `path` by a previous, non-rotating run is adopted as a closed segment, as is
+	/// a live segment a previous run left at the current path on non-unix.
+	pub(super) fn open(path: PathBuf, policy: RotationPolicy) -> io::Result<Self> {
+		let Some(rotation) = policy.rotation else {
+			let live = open_append(&path, now_ms())?;
+			live.file.set_len(0)?;
+			return Ok(Self {
+				live: Arc::new(Mutex::new(LiveSegment { written: 0, ..live })),
+				rotation: Rotation::default(),
+				segments: None,
+			});
+		};
+		let layout = Arc::new(SegmentLayout::new(path));
+		layout.adopt_plain_file(&layout.path)?;
+		layout.adopt_plain_file(&layout.current)?;
+		let live = layout.open_live(now_ms(), None)?;
+
+		let (housekeeper, rx) = mpsc::channel::<i64>();
+		let housekeeping_layout = Arc::clone(&layout);
+		thread::Builder::new().name("log-housekeeper".to_owned()).spawn(move || {
+			while let Ok(live) = rx.recv() {
+				// Only the newest live segment matters if several rotations queued up.
+				let live = rx.try_iter().last().unwrap_or(live);
+				if let Err(e) = housekeeping_layout.housekeep(policy, live) {
+					eprintln!("[log-rotation] Failed to prune old segments of {}: {e}", housekeeping_layout.path.display());
+				}
+			}
+		})?;
+		// Leftovers of previous runs are subject to the same retention.
+		let _ = housekeeper.send(live.started_ms);
+
+		Ok(Self {
+			live: Arc::new(Mutex::new(live)),
+			rotation,
+			segments: Some(Segments { layout, housekeeper }),
+		})
+	}
+
+	fn is_due(&self, live: &LiveSegment, incoming: usize) -> bool {
+		const HOUR_MS: i64 = 60 * 60 * 1000;
+		const DAY_MS: i64 = 24 * HOUR_MS;
+		match self.rotation {
+			Rotation::MaxSize(max) => live.written > 0 && live.written + incoming as u64 > max,
+			Rotation::Daily => now_ms().div_euclid(DAY_MS) != live.started_ms.div_euclid(DAY_MS),
+			Rotation::Hourly => now_ms().div_euclid(HOUR_MS) != live.started_ms.div_euclid(HOUR_MS),
+		}
+	}
+
+	fn rotate(&self, live: &mut LiveSegment) {
+		// Keep segment names unique and ordered even for several rotations within one millisecond.
+		let started_ms = now_ms().max(live.started_ms + 1);
+		let Some(segments) = &self.segments else {
+			// Opened for appending, so writes carry on from the start.
+			if let Err(e) = live.file.set_len(0) {
+				eprintln!("[log-rotation] Couldn't truncate the log, continuing to grow it: {e}");
+			}
+			live.started_ms = started_ms;
+			live.written = 0;
+			return;
+		};
+		match segments.layout.open_live(started_ms, Some(live.started_ms)) {
+			Ok(next) => {
+				*live = next;
+				let _ = segments.housekeeper.send(live.started_ms);
+			}
+			Err(e) => {
+				eprintln!(
+					"[log-rotation] Couldn't open a new segment next to {}, continuing in the current one: {e}",
+					segments.layout.path.display()
+				);
+				// Postpone the next attempt by a full period instead of retrying on every event.
+				live.started_ms = started_ms;
+				live.written = 0;
+			}
+		}
+	}
+}
+
+impl Write for RotatingFileWriter {
+	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+		let mut live = self.live.lock().unwrap();
+		if self.is_due(&live, buf.len()) {
+			self.rotate(&mut live);
+		}
+		let written = live.file.write(buf)?;
+		live.written += written as u64;
+		Ok(written)
+	}
+
+	fn flush(&mut self) -> io::Result<()> {
+		self.live.lock().unwrap().file.flush()
+	}
+}
+
+struct LiveSegment {
+	file: File,
+	started_ms: i64,
+	written: u64,
+}
+
+/// Where the segments for one configured log path live, and how they are named.
+struct SegmentLayout {
+	/// The configured path, which names the segments.
+	path: PathBuf,
+	/// [`current_log_path`] of `path`.
+	current: PathBuf,
+	dir: PathBuf,
+	/// Configured file name without its `.log` extension; empty for the default `.log`.
+	stem: String,
+}
+
+impl SegmentLayout {
+	fn new(path: PathBuf) -> Self {
+		let dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+		let stem = stem_of(&path);
+		let current = current_log_path(&path);
+		Self { path, current, dir, stem }
+	}
+
+	fn segment_path(&self, started_ms: i64) -> PathBuf {
+		let stamp = stamp(started_ms);
+		match self.stem.as_str() {
+			"" => self.dir.join(format!("{stamp}.log")),
+			stem => self.dir.join(format!("{stem}.{stamp}.log")),
+		}
+	}
+
+	/// The timestamp part of `name` if it is one of our segments, live, closed or gzipped.
+	fn segment_stamp<'a>(&self, name: &'a str) -> Option<&'a str> {
+		let rest = name.strip_suffix(".gz").unwrap_or(name).strip_suffix(".log")?;
+		let stamp = match self.stem.as_str() {
+			"" => rest,
+			stem => rest.strip_prefix(stem)?.strip_prefix('.')?,
+		};
+		let is_stamp = stamp.len() == STAMP_SHAPE.len() && stamp.bytes().zip(STAMP_SHAPE.bytes()).all(|(c, s)| if s == b'0' { c.is_ascii_digit() } else { c == s });
+		is_stamp.then_some(stamp)
+	}
+
+	/// Opens the segment starting at `started_ms`, and points the current path at it.
+	#[cfg(unix)]
+	fn open_live(&self, started_ms: i64, _closing_ms: Option<i64>) -> io::Result<LiveSegment> {
+		let segment = self.segment_path(started_ms);
+		let live = open_append(&segment, started_ms)?;
+		if let Err(e) = self.point_current_at(&segment) {
+			eprintln!("[log-rotation] Couldn't point {} at the new segment: {e}", self.current.display());
+		}
+		Ok(live)
+	}
+
+	/// Atomically repoints the current path at `segment`, via a relative symlink.
+	#[cfg(unix)]
+	fn point_current_at(&self, segment: &Path) -> io::Result<()> {
+		let name = self.current.file_name().unwrap_or_default().to_string_lossy();
+		let tmp = self.dir.join(format!(".{name}.link-tmp"));
+		let _ = std::fs::remove_file(&tmp);
+		std::os::unix::fs::symlink(segment.file_name().unwrap_or_default(), &tmp)?;
+		std::fs::rename(&tmp, &self.current)
+	}
+
+	/// Renames the segment that started at `closing_ms` away from the current path, and opens a fresh one there.
+	#[cfg(not(unix))]
+	fn open_live(&self, started_ms: i64, closing_ms: Option<i64>) -> io::Result<LiveSegment> {
+		if let Some(closing_ms) = closing_ms {
+			std::fs::rename(&self.current, self.segment_path(closing_ms))?;
+		}
+		open_append(&self.current, started_ms)
+	}
+
+	fn adopt_plain_file(&self, path: &Path) -> io::Result<()> {
+		let Ok(meta) = std::fs::symlink_metadata(path) else { return Ok(()) };
+		if !meta.is_file() {
+			return Ok(());
+		}
+		let modified_ms = meta.modified().ok().and_then(|t| t.duration_since(UNIX_EPOCH).ok()).map_or(0, |d| d.as_millis() as i64);
+		std::fs::rename(path, self.segment_path(modified_ms))
+	}
+
+	/// Compresses (if enabled) and prunes every segment older than the live one. Anything newer may already be live itself.
+	fn housekeep(&self, policy: RotationPolicy, live_started_ms: i64) -> io::Result<()> {
+		let live_stamp = stamp(live_started_ms);
+		let live_stamp = live_stamp.as_str();
+
+		let mut closed: Vec<(String, PathBuf)> = std::fs::read_dir(&self.dir)?
+			.filter_map(|entry| {
+				let path = entry.ok()?.path();
+				let stamp = self.segment_stamp(path.file_name()?.to_str()?)?.to_owned();
+				(stamp.as_str() < live_stamp).then_some((stamp, path))
+			})
+			.collect();
+		closed.sort_unstable_by(|a, b| b.0.cmp(&a.0));
+
+		for (i, (_, path)) in closed.into_iter().enumerate() {
+			if i >= policy.keep {
+				std::fs::remove_file(&path)?;
+			} else if policy.gzip && path.extension().is_some_and(|ext| ext == "log") {
+				gzip_in_place(&path)?;
+			}
+		}
+		Ok(())
+	}
+}
+
+fn stamp(started_ms: i64) -> String {
+	jiff::Timestamp::from_millisecond(started_ms)
+		.unwrap_or(jiff::Timestamp::UNIX_EPOCH)
+		.strftime(STAMP_FORMAT)
+		.to_string()
+}
+
+fn open_append(path: &Path, started_ms: i64) -> io::Result<LiveSegment> {
+	let file = OpenOptions::new().create(true).append(true).open(path)?;
+	let written = file.metadata()?.len();
+	Ok(LiveSegment { file, started_ms, written })
+}
+
+/// Replaces `path` with `{path}.gz`. Written under a temporary name first, so a crash never leaves a truncated archive.
+fn gzip_in_place(path: &Path) -> io::Result<()> {
+	let mut gz_name = path.as_os_str().to_owned();
+	gz_name.push(".gz");
+	let gz_path = PathBuf::from(gz_name);
+	let tmp_path = gz_path.with_extension("gz-partial");
+
+	let mut encoder = flate2::write::GzEncoder::new(File::create(&tmp_path)?, flate2::Compression::default());
+	io::copy(&mut File::open(path)?, &mut encoder)?;
+	encoder.finish()?.sync_all()?;
+	std::fs::rename(&tmp_path, &gz_path)?;
+	std::fs::remove_file(path)
+}
+
+fn now_ms() -> i64 {
+	SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_millis() as i64)
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{io::Read as _, time::Duration};
+
+	use super::*;
+
+	/// Housekeeping runs on its own thread; wait for the directory to settle into the expected shape.
+	fn wait_for(dir: &Path, mut done: impl FnMut(&[String]) -> bool) -> Vec<String> {
+		let list = || {
+			let mut names: Vec<String> = std::fs::read_dir(dir).unwrap().map(|e| e.unwrap().file_name().to_string_lossy().into_owned()).collect();
+			names.sort();
+			names
+		};
+		for _ in 0..200 {
+			let names = list();
+			if done(&names) {
+				return names;
+			}
+			thread::sleep(Duration::from_millis(10));
+		}
+		list()
+	}
+
+	#[test]
+	fn segment_names_round_trip() {
+		let layout = SegmentLayout::new(PathBuf::from("/var/log/app/orders.log"));
+		let path = layout.segment_path(1_700_000_000_123);
+		let name = path.file_name().unwrap().to_str().unwrap();
+		assert_eq!(name, "orders.2023-11-14T22-13-20.123.log");
+		assert_eq!(layout.segment_stamp(name), Some("2023-11-14T22-13-20.123"));
+		assert_eq!(layout.segment_stamp(&format!("{name}.gz")), Some("2023-11-14T22-13-20.123"));
+		assert_eq!(layout.segment_stamp("orders.log"), None);
+		assert_eq!(layout.segment_stamp("orders.current.log"), None);
+		assert_eq!(layout.current, Path::new("/var/log/app/orders.current.log"));
+		assert_eq!(layout.segment_stamp("other.2023-11-14T22-13-20.123.log"), None);
+
+		// The default `.log` name has an empty stem; neither sibling logs nor their segments may match it.
+		let layout = SegmentLayout::new(PathBuf::from("/var/log/app/.log"));
+		assert_eq!(layout.segment_path(0).file_name().unwrap(), "1970-01-01T00-00-00.000.log");
+		assert_eq!(layout.segment_stamp("orders.2023-11-14T22-13-20.123.log"), None);
+		assert_eq!(layout.segment_stamp("_log_directives"), None);
+		assert_eq!(layout.segment_stamp("current.log"), None);
+		assert_eq!(layout.current, Path::new("/var/log/app/current.log"));
+	}
+
+	#[test]
+	fn without_rotation_the_configured_path_is_written_and_truncated() {
+		let tmp = tempfile::tempdir().unwrap();
+		let path = tmp.path().join("app.log");
+		std::fs::write(&path, "from a previous run\n").unwrap();
+
+		let mut writer = RotatingFileWriter::open(path.clone(), RotationPolicy::default()).unwrap();
+		writer.write_all(b"first\n").unwrap();
+		assert_eq!(std::fs::read_to_string(&path).unwrap(), "first\n");
+
+		writer.rotation = Rotation::MaxSize(8);
+		writer.write_all(b"second\n").unwrap();
+		assert_eq!(std::fs::read_to_string(&path).unwrap(), "second\n");
+		assert_eq!(wait_for(tmp.path(), |_| true), ["app.log"]);
+	}
+
+	#[test]
+	fn max_size_rotates_and_prunes_to_keep() {
+		let tmp = tempfile::tempdir().unwrap();
+		let path = tmp.path().join("app.log");
+		std::fs::write(&path, "from a previous, non-rotating run\n").unwrap();
+
+		let policy = RotationPolicy {
+			rotation: Some(Rotation::MaxSize(16)),
+			keep: 2,
+			gzip: false,
+		};
+		let mut writer = RotatingFileWriter::open(path.clone(), policy).unwrap();
+		for i in 0..5 {
+			writeln!(writer, "event number {i}").unwrap();
+		}
+
+		// current + live + 2 kept; the adopted plain file and the two oldest rotations are pruned.
+		let names = wait_for(tmp.path(), |names| names.len() == 4);
+		assert_eq!(names.len(), 4, "{names:?}");
+		assert!(!path.exists(), "{names:?}");
+		let current = tmp.path().join("app.current.log");
+		assert!(std::fs::symlink_metadata(&current).unwrap().file_type().is_symlink());
+		assert_eq!(std::fs::read_to_string(&current).unwrap(), "event number 4\n");
+	}
+
+	#[test]
+	fn closed_segments_are_gzipped() {
+		let tmp = tempfile::tempdir().unwrap();
+		let path = tmp.path().join(".log");
+		let policy = RotationPolicy {
+			rotation: Some(Rotation::MaxSize(1)),
+			keep: 1,
+			gzip: true,
+		};
+		let mut writer = RotatingFileWriter::open(path, policy).unwrap();
+		writer.write_all(b"first\n").unwrap();
+		writer.write_all(b"second\n").unwrap();
+
+		let names = wait_for(tmp.path(), |names| names.iter().any(|n| n.ends_with(".log.gz")));
+		let gz = names.iter().find(|n| n.ends_with(".log.gz")).unwrap_or_else(|| panic!("no archive in {names:?}"));
+		let mut decoded = String::new();
+		flate2::read::GzDecoder::new(File::open(tmp.path().join(gz)).unwrap()).read_to_string(&mut decoded).unwrap();
+		assert_eq!(decoded, "first\n");
+		assert_eq!(std::fs::read_to_string(tmp.path().join("current.log")).unwrap(), "second\n");
+	}
+}
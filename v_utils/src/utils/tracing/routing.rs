@@ -0,0 +1,99 @@
+//! Extra log files, each taking the events of one target on top of the main log.
+
+use std::{path::Path, sync::Arc};
+
+use tracing::{Metadata, Subscriber, error};
+use tracing_subscriber::{
+	EnvFilter, Layer,
+	filter::{FilterExt as _, filter_fn},
+	registry::LookupSpan,
+};
+
+use super::{LogFormat, LogTimer, Redactor, RotatingFileWriter, RotationPolicy, Route, fmt_layer, normalize_directives};
+
+pub(super) fn is_within(target: &str, route_target: &str) -> bool {
+	target.strip_prefix(route_target).is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+}
+
+/// # Panics
+/// Like the main log, if the file can't be opened for writing.
+pub(super) fn layer<S>(
+	route: &Route,
+	log_dir: Option<&Path>,
+	main: (LogFormat, RotationPolicy),
+	timer: LogTimer,
+	redactor: &Arc<Redactor>,
+	logs_during_init: &mut Vec<Box<dyn FnOnce()>>,
+) -> Box<dyn Layer<S> + Send + Sync>
+where
+	S: Subscriber + for<'a> LookupSpan<'a>, {
+	let path = match log_dir {
+		Some(dir) if route.path.is_relative() => dir.join(&route.path),
+		_ => route.path.clone(),
+	};
+	let writer = RotatingFileWriter::open(path.clone(), route.rotation.unwrap_or(main.1))
+		.unwrap_or_else(|e| panic!("Couldn't open {} for writing the `{}` route ({e})", path.display(), route.target));
+	let fmt = fmt_layer(route.format.unwrap_or(main.0), timer, redactor.writer(move || writer.clone()), false, true);
+
+	// Spans of any target pass, so events keep their full span context.
+	let target = route.target.clone();
+	let in_target = filter_fn(move |meta: &Metadata<'_>| meta.is_span() || is_within(meta.target(), &target));
+	let Some(directives) = route.directives.as_deref() else {
+		return fmt.with_filter(in_target).boxed();
+	};
+	match EnvFilter::builder().parse(normalize_directives(directives)) {
+		Ok(env_filter) => fmt.with_filter(in_target.and(env_filter)).boxed(),
+		Err(e) => {
+			let (target, directives) = (route.target.clone(), directives.to_owned());
+			logs_during_init.push(Box::new(move || {
+				error!("Error parsing directives of the `{target}` route, routing all of it:\n```\n{directives}\n```\n{e}")
+			}));
+			fmt.with_filter(in_target).boxed()
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use tracing_subscriber::layer::SubscriberExt as _;
+
+	use super::*;
+	use crate::utils::tracing::Redaction;
+
+	#[test]
+	fn routes_by_target_with_own_directives_and_format() {
+		let dir = tempfile::tempdir().unwrap();
+		let redactor = Redactor::new(&Redaction::default());
+		let main = (LogFormat::Text, RotationPolicy::default());
+		let mut logs_during_init = Vec::new();
+		let orders = Route::new("bot::orders", "orders.log").format(LogFormat::JsonLines);
+		let ws = Route::new("bot::ws", dir.path().join("ws.log")).directives("info");
+		let layers: Vec<_> = [orders, ws]
+			.iter()
+			.map(|route| layer(route, Some(dir.path()), main, LogTimer::default(), &redactor, &mut logs_during_init))
+			.collect();
+
+		tracing::subscriber::with_default(tracing_subscriber::registry().with(redactor.layer()).with(layers), || {
+			tracing::info_span!("session", id = 3).in_scope(|| {
+				tracing::info!(target: "bot::orders", qty = 2, "filled");
+				tracing::info!(target: "bot::orders::audit", "checked");
+				tracing::info!(target: "bot::orders_v2", "not a submodule");
+				tracing::debug!(target: "bot::ws", "ping");
+				tracing::info!(target: "bot::ws", "reconnected");
+			});
+		});
+		assert!(logs_during_init.is_empty());
+
+		let orders = std::fs::read_to_string(dir.path().join("orders.log")).unwrap();
+		let messages: Vec<String> = orders
+			.lines()
+			.map(|l| serde_json::from_str::<serde_json::Value>(l).unwrap()["fields"]["message"].as_str().unwrap().to_owned())
+			.collect();
+		assert_eq!(messages, ["filled", "checked"]);
+		assert!(orders.contains(r#""name":"session""#), "{orders}");
+
+		let ws = std::fs::read_to_string(dir.path().join("ws.log")).unwrap();
+		assert_eq!(ws.lines().count(), 1, "{ws}");
+		assert!(ws.contains("session{id=3}") && ws.contains("reconnected"), "{ws}");
+	}
+}
@@ -0,0 +1,356 @@
+//! Durations of closed spans: a WARN for those outliving their target's threshold, and p50/p99 summaries.
+//!
+//! Thresholds are resolved like the log directives: `.cargo/span_thresholds` first, then `_span_thresholds` in the log
+//! directory, then what was passed to [`LogDestination::span_timing`](super::LogDestination::span_timing). Edits to
+//! either file apply without a restart.
+
+use std::{
+	collections::BTreeMap,
+	fmt,
+	path::{Path, PathBuf},
+	sync::{Arc, Mutex, OnceLock, RwLock},
+	time::{Duration, Instant},
+};
+
+use tracing::{Subscriber, error, info, span, warn};
+use tracing_subscriber::{Layer, layer::Context, registry::LookupSpan};
+
+use super::{SpanThresholds, SpanThresholdsError};
+use crate::primitives::{Timeframe, Timelike};
+
+const CARGO_THRESHOLDS_PATH: &str = ".cargo/span_thresholds";
+const THRESHOLDS_FILENAME: &str = "_span_thresholds";
+
+impl SpanThresholds {
+	fn for_target(&self, target: &str) -> Option<Duration> {
+		self.by_target.iter().find(|(t, _)| super::routing::is_within(target, t)).map(|(_, d)| *d).or(self.default)
+	}
+}
+
+/// The first of `files` that exists, parsed, along with its path.
+fn read_thresholds(files: &[PathBuf]) -> Option<(String, Result<SpanThresholds, SpanThresholdsError>)> {
+	files.iter().find_map(|path| {
+		let s = std::fs::read_to_string(path).ok()?;
+		Some((path.display().to_string(), s.parse()))
+	})
+}
+
+/// `µs` below a millisecond, then [`Timeframe`]'s `ms`/`s`, and [`Timelike`]'s `mm:ss` past a minute.
+fn fmt_duration(d: Duration) -> String {
+	if d < Duration::from_millis(1) {
+		format!("{}µs", d.as_micros())
+	} else if d < Duration::from_secs(60) {
+		Timeframe::from(d).to_string()
+	} else {
+		Timelike(d.as_secs() as u32).to_string()
+	}
+}
+
+/// Log-linear buckets of nanoseconds: 8 per power of two, so quantiles are off by at most 1/8th.
+#[derive(Clone, Debug, Default)]
+struct Histogram {
+	buckets: Vec<u64>,
+	count: u64,
+	max: Duration,
+}
+
+const SUB_BUCKET_BITS: u32 = 3;
+const SUB_BUCKETS: u64 = 1 << SUB_BUCKET_BITS;
+
+impl Histogram {
+	fn record(&mut self, d: Duration) {
+		let nanos = u64::try_from(d.as_nanos()).unwrap_or(u64::MAX);
+		let idx = Self::bucket_of(nanos);
+		if self.buckets.len() <= idx {
+			self.buckets.resize(idx + 1, 0);
+		}
+		self.buckets[idx] += 1;
+		self.count += 1;
+		self.max = self.max.max(d);
+	}
+
+	fn bucket_of(nanos: u64) -> usize {
+		if nanos < SUB_BUCKETS {
+			return nanos as usize;
+		}
+		let exp = 63 - nanos.leading_zeros();
+		let sub = (nanos >> (exp - SUB_BUCKET_BITS)) & (SUB_BUCKETS - 1);
+		((exp - SUB_BUCKET_BITS + 1) as u64 * SUB_BUCKETS + sub) as usize
+	}
+
+	/// Upper bound of the bucket.
+	fn bucket_ceiling(idx: usize) -> u64 {
+		let idx = idx as u64;
+		if idx < SUB_BUCKETS {
+			return idx;
+		}
+		let shift = idx / SUB_BUCKETS - 1;
+		(SUB_BUCKETS + idx % SUB_BUCKETS + 1) << shift
+	}
+
+	fn quantile(&self, q: f64) -> Duration {
+		let rank = ((self.count as f64 * q).ceil() as u64).max(1);
+		let mut seen = 0;
+		for (idx, n) in self.buckets.iter().enumerate() {
+			seen += n;
+			if seen >= rank {
+				return Duration::from_nanos(Self::bucket_ceiling(idx)).min(self.max);
+			}
+		}
+		self.max
+	}
+}
+
+#[derive(Debug, Default)]
+struct Timings {
+	/// By `target::span_name`.
+	by_span: Mutex<BTreeMap<String, Histogram>>,
+}
+
+impl fmt::Display for Timings {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let by_span = self.by_span.lock().unwrap();
+		let width = by_span.keys().map(|k| k.chars().count()).max().unwrap_or(0);
+		write!(f, "Span timings:")?;
+		for (span, h) in by_span.iter() {
+			write!(
+				f,
+				"\n  {span:<width$}  n={:<6} p50={:<8} p99={:<8} max={}",
+				h.count,
+				fmt_duration(h.quantile(0.5)),
+				fmt_duration(h.quantile(0.99)),
+				fmt_duration(h.max)
+			)?;
+		}
+		Ok(())
+	}
+}
+
+/// Of the installed layer, for [`dump_summary`].
+static TIMINGS: OnceLock<Arc<Timings>> = OnceLock::new();
+
+/// Logs the p50/p99 of every span seen so far, if span timing is on.
+pub(super) fn dump_summary() {
+	if let Some(timings) = TIMINGS.get() {
+		tracing::info!(target: "span_timing", "{timings}");
+	}
+}
+
+pub(super) struct SpanTimingLayer {
+	thresholds: Arc<RwLock<SpanThresholds>>,
+	timings: Arc<Timings>,
+}
+
+impl SpanTimingLayer {
+	/// Also what [`dump_summary`] (and so SIGUSR1 and the shutdown guard) reports on. `configured` is what the
+	/// thresholds files are read over, and what they fall back to once removed.
+	pub(super) fn install(configured: SpanThresholds, log_dir: Option<&Path>, logs_during_init: &mut Vec<Box<dyn FnOnce()>>) -> Self {
+		let files: Vec<PathBuf> = std::iter::once(PathBuf::from(CARGO_THRESHOLDS_PATH))
+			.chain(log_dir.map(|d| d.join(THRESHOLDS_FILENAME)))
+			.collect();
+		let initial = match read_thresholds(&files) {
+			Some((source, Ok(thresholds))) => {
+				logs_during_init.push(Box::new(move || info!("Using span thresholds from `{source}`")));
+				thresholds
+			}
+			Some((source, Err(e))) => {
+				logs_during_init.push(Box::new(move || error!("Error parsing span thresholds from `{source}`, using the configured ones: {e}")));
+				configured.clone()
+			}
+			None => configured.clone(),
+		};
+		let thresholds = Arc::new(RwLock::new(initial));
+
+		let shared = Arc::clone(&thresholds);
+		let watched = files.clone();
+		let spawned = super::file_watch::on_change("span-thresholds-watcher", watched, move || {
+			let reloaded = match read_thresholds(&files) {
+				Some((source, Ok(thresholds))) => {
+					info!("Reloaded span thresholds from `{source}`");
+					thresholds
+				}
+				Some((source, Err(e))) => {
+					error!("Keeping the current span thresholds, `{source}` didn't parse: {e}");
+					return;
+				}
+				None => configured.clone(),
+			};
+			*shared.write().unwrap() = reloaded;
+		});
+		if let Err(e) = spawned {
+			logs_during_init.push(Box::new(move || error!("Couldn't start watching span thresholds files, they'll only be read at init: {e}")));
+		}
+
+		let timings = Arc::clone(TIMINGS.get_or_init(Default::default));
+		#[cfg(unix)]
+		sigusr1::watch();
+		Self { thresholds, timings }
+	}
+}
+
+struct Opened(Instant);
+
+impl<S> Layer<S> for SpanTimingLayer
+where
+	S: Subscriber + for<'a> LookupSpan<'a>,
+{
+	fn on_new_span(&self, _attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+		if let Some(span) = ctx.span(id) {
+			span.extensions_mut().insert(Opened(Instant::now()));
+		}
+	}
+
+	fn on_close(&self, id: span::Id, ctx: Context<'_, S>) {
+		let Some(span) = ctx.span(&id) else { return };
+		let Some(elapsed) = span.extensions().get::<Opened>().map(|o| o.0.elapsed()) else { return };
+		let meta = span.metadata();
+		self.timings
+			.by_span
+			.lock()
+			.unwrap()
+			.entry(format!("{}::{}", meta.target(), meta.name()))
+			.or_default()
+			.record(elapsed);
+
+		let threshold = self.thresholds.read().unwrap().for_target(meta.target());
+		if let Some(threshold) = threshold {
+			if elapsed > threshold {
+				warn!(
+					target: "span_timing",
+					"Slow span `{}::{}`: took {} (threshold {})",
+					meta.target(),
+					meta.name(),
+					fmt_duration(elapsed),
+					fmt_duration(threshold)
+				);
+			}
+		}
+	}
+}
+
+#[cfg(unix)]
+mod sigusr1 {
+	use std::{
+		sync::{
+			Once,
+			atomic::{AtomicBool, Ordering},
+		},
+		time::Duration,
+	};
+
+	static REQUESTED: AtomicBool = AtomicBool::new(false);
+
+	/// Handles SIGUSR1, dumping the summary within a second of receiving it.
+	///
+	/// The handler goes through `signal-hook-registry`, so whatever else is registered there (tokio's signal streams,
+	/// for one) still runs, as does a handler installed earlier by other means. SIGUSR1's default action of terminating
+	/// the process is gone for good though.
+	pub(super) fn watch() {
+		static ONCE: Once = Once::new();
+		ONCE.call_once(|| {
+			// Nothing but an atomic store is async-signal-safe; the logging happens on the watcher thread.
+			// SAFETY: the action only touches an atomic.
+			if let Err(e) = unsafe { signal_hook_registry::register(libc::SIGUSR1, || REQUESTED.store(true, Ordering::Relaxed)) } {
+				eprintln!("[v_utils] Failed to register the span timing SIGUSR1 handler: {e}");
+				return;
+			}
+			let spawned = std::thread::Builder::new().name("span-timing-sigusr1".to_owned()).spawn(|| {
+				loop {
+					std::thread::sleep(Duration::from_secs(1));
+					if REQUESTED.swap(false, Ordering::Relaxed) {
+						super::dump_summary();
+					}
+				}
+			});
+			if let Err(e) = spawned {
+				eprintln!("[v_utils] Failed to spawn span timing SIGUSR1 watcher: {e}");
+			}
+		});
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use tracing_subscriber::layer::SubscriberExt as _;
+
+	use super::*;
+	use crate::utils::tracing::tests::TestSink;
+
+	#[test]
+	fn thresholds_pick_most_specific_target() {
+		let thresholds: SpanThresholds = "500ms,bot=2s\nbot::orders=50ms".parse().unwrap();
+		assert_eq!(thresholds.for_target("bot::orders::fill"), Some(Duration::from_millis(50)));
+		assert_eq!(thresholds.for_target("bot::ws"), Some(Duration::from_secs(2)));
+		assert_eq!(thresholds.for_target("bot_v2"), Some(Duration::from_millis(500)));
+		assert_eq!("bot=2s".parse::<SpanThresholds>().unwrap().for_target("other"), None);
+
+		let err = "bot::orders=50".parse::<SpanThresholds>().unwrap_err();
+		assert_eq!(err.to_string(), "invalid span threshold `bot::orders=50`: expected a duration with a unit, e.g. `250ms` or `2s`");
+	}
+
+	#[test]
+	fn thresholds_files_read_in_order() {
+		let tmp = tempfile::tempdir().unwrap();
+		let (first, second) = (tmp.path().join("first"), tmp.path().join("second"));
+		assert!(read_thresholds(&[first.clone(), second.clone()]).is_none());
+
+		std::fs::write(&second, "# slow enough to matter\n500ms\nbot::orders=50ms\n").unwrap();
+		let (source, thresholds) = read_thresholds(&[first.clone(), second.clone()]).unwrap();
+		assert_eq!(source, second.display().to_string());
+		assert_eq!(thresholds.unwrap().for_target("bot::orders"), Some(Duration::from_millis(50)));
+
+		std::fs::write(&first, "bot=fast").unwrap();
+		let (source, thresholds) = read_thresholds(&[first.clone(), second]).unwrap();
+		assert_eq!(source, first.display().to_string());
+		assert!(thresholds.is_err());
+	}
+
+	#[test]
+	fn histogram_quantiles_within_a_bucket() {
+		let mut h = Histogram::default();
+		for ms in 1..=100 {
+			h.record(Duration::from_millis(ms));
+		}
+		let within = |got: Duration, expected_ms: u64| {
+			let expected = Duration::from_millis(expected_ms);
+			assert!(got >= expected && got <= expected + expected / SUB_BUCKETS as u32, "{got:?} vs {expected:?}");
+		};
+		within(h.quantile(0.5), 50);
+		within(h.quantile(0.99), 99);
+		assert_eq!(h.quantile(1.0), Duration::from_millis(100));
+		assert_eq!([0, 7, 8, 15, 16, 17, 1_000].map(Histogram::bucket_of), [0, 7, 8, 15, 16, 16, 63]);
+	}
+
+	#[test]
+	fn formats_durations_with_primitives() {
+		assert_eq!(fmt_duration(Duration::from_micros(250)), "250µs");
+		assert_eq!(fmt_duration(Duration::from_millis(1_500)), "1500ms");
+		assert_eq!(fmt_duration(Duration::from_secs(5)), "5s");
+		assert_eq!(fmt_duration(Duration::from_secs(65)), "01:05");
+	}
+
+	#[test]
+	fn warns_on_slow_spans_and_summarizes() {
+		let sink = TestSink::default();
+		let layer = SpanTimingLayer {
+			thresholds: Arc::new(RwLock::new("bot::orders=5ms".parse().unwrap())),
+			timings: Arc::default(),
+		};
+		let timings = Arc::clone(&layer.timings);
+		let text = crate::utils::tracing::format::fmt_layer(crate::utils::tracing::LogFormat::Text, Default::default(), sink.clone(), false, false);
+		tracing::subscriber::with_default(tracing_subscriber::registry().with(layer).with(text), || {
+			tracing::info_span!(target: "bot::orders", "fill").in_scope(|| std::thread::sleep(Duration::from_millis(10)));
+			tracing::info_span!(target: "bot::orders", "fill").in_scope(|| {});
+			tracing::info_span!(target: "bot::ws", "ping").in_scope(|| std::thread::sleep(Duration::from_millis(10)));
+		});
+
+		let out = sink.output();
+		assert_eq!(out.lines().count(), 1, "{out}");
+		assert!(out.contains("WARN span_timing: Slow span `bot::orders::fill`: took 1"), "{out}");
+		assert!(out.contains("(threshold 5ms)"), "{out}");
+
+		let summary = timings.to_string();
+		assert!(summary.starts_with("Span timings:\n  bot::orders::fill  n=2 "), "{summary}");
+		assert!(summary.contains("\n  bot::ws::ping      n=1 "), "{summary}");
+	}
+}
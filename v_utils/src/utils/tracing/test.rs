@@ -0,0 +1,302 @@
+//! Capturing log events in memory, to assert on them in tests.
+//!
+//! ```ignore
+//! let logs = v_utils::utils::tracing::test::capture();
+//! load_config();
+//! v_utils::assert_logged!(warn, contains = "Missing configuration field");
+//! insta::assert_snapshot!(logs.render());
+//! ```
+//!
+//! The subscriber is thread-scoped: events emitted on other threads (e.g. by a multi-threaded tokio runtime) aren't captured.
+
+use std::{
+	cell::RefCell,
+	collections::BTreeMap,
+	fmt,
+	sync::{Arc, Mutex},
+};
+
+use tracing::{
+	Event, Level, Subscriber,
+	field::{Field, Visit},
+	span,
+};
+use tracing_subscriber::{
+	Layer,
+	layer::{Context, SubscriberExt as _},
+	registry::LookupSpan,
+};
+
+/// An event seen by [`capture`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CapturedEvent {
+	pub level: Level,
+	pub target: String,
+	pub message: String,
+	/// Event fields other than `message`, `Debug`-formatted (strings unquoted).
+	pub fields: BTreeMap<String, String>,
+	/// Spans the event was emitted in, outermost first.
+	pub spans: Vec<CapturedSpan>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CapturedSpan {
+	pub name: String,
+	pub fields: BTreeMap<String, String>,
+}
+
+impl fmt::Display for CapturedEvent {
+	/// `LEVEL span{k=v}:span: target: message k=v`, free of timestamps and source locations so it snapshots stably.
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{:>5} ", self.level)?;
+		for span in &self.spans {
+			write!(f, "{}", span.name)?;
+			if !span.fields.is_empty() {
+				write!(f, "{{{}}}", join_fields(&span.fields))?;
+			}
+			f.write_str(":")?;
+		}
+		if !self.spans.is_empty() {
+			f.write_str(" ")?;
+		}
+		write!(f, "{}: {}", self.target, self.message)?;
+		if !self.fields.is_empty() {
+			write!(f, " {}", join_fields(&self.fields))?;
+		}
+		Ok(())
+	}
+}
+
+fn join_fields(fields: &BTreeMap<String, String>) -> String {
+	fields.iter().map(|(k, v)| format!("{k}={v}")).collect::<Vec<_>>().join(" ")
+}
+
+type Events = Arc<Mutex<Vec<CapturedEvent>>>;
+
+thread_local! {
+	/// Innermost live capture on this thread, for [`assert_logged!`](crate::assert_logged).
+	static CURRENT: RefCell<Option<Events>> = const { RefCell::new(None) };
+}
+
+/// Captures every event on the current thread, at all levels, until dropped.
+#[must_use = "events are only captured while the guard is alive"]
+pub fn capture() -> LogCapture {
+	let events = Events::default();
+	// `ErrorLayer` so `SpanTrace`s captured under test (e.g. by the panic hook) see the spans.
+	let subscriber = tracing_subscriber::registry()
+		.with(CaptureLayer { events: Arc::clone(&events) })
+		.with(tracing_error::ErrorLayer::default());
+	let guard = tracing::subscriber::set_default(subscriber);
+	let outer = CURRENT.with(|current| current.borrow_mut().replace(Arc::clone(&events)));
+	LogCapture { events, outer, _guard: guard }
+}
+
+/// Guard returned by [`capture`]. Nested captures shadow outer ones until dropped.
+pub struct LogCapture {
+	events: Events,
+	outer: Option<Events>,
+	_guard: tracing::subscriber::DefaultGuard,
+}
+
+impl LogCapture {
+	pub fn events(&self) -> Vec<CapturedEvent> {
+		self.events.lock().unwrap().clone()
+	}
+
+	/// One [`CapturedEvent`] per line; meant for `insta::assert_snapshot!`.
+	pub fn render(&self) -> String {
+		render(&self.events.lock().unwrap())
+	}
+
+	pub fn clear(&self) {
+		self.events.lock().unwrap().clear();
+	}
+}
+
+impl Drop for LogCapture {
+	fn drop(&mut self) {
+		CURRENT.with(|current| *current.borrow_mut() = self.outer.take());
+	}
+}
+
+fn render(events: &[CapturedEvent]) -> String {
+	events.iter().map(|e| format!("{e}\n")).collect()
+}
+
+#[doc(hidden)]
+pub enum Expected<'a> {
+	Contains(&'a str),
+	Message(&'a str),
+}
+
+/// Backs [`assert_logged!`](crate::assert_logged).
+#[doc(hidden)]
+#[track_caller]
+pub fn __assert_logged(level: Option<&str>, expected: Expected<'_>) {
+	let level = level.map(|l| l.parse::<Level>().unwrap_or_else(|_| panic!("`{l}` is not a log level")));
+	let events = CURRENT
+		.with(|current| current.borrow().clone())
+		.expect("assert_logged! needs a live `v_utils::utils::tracing::test::capture()` guard on this thread");
+	// Not held across the panic below: a panic hook logging it (see `LogDestination::panic_hook`) would deadlock on it.
+	let (found, captured) = {
+		let events = events.lock().unwrap();
+		let found = events.iter().any(|e| {
+			level.is_none_or(|l| e.level == l)
+				&& match expected {
+					Expected::Contains(needle) => e.message.contains(needle),
+					Expected::Message(message) => e.message == message,
+				}
+		});
+		(found, if found { String::new() } else { render(&events) })
+	};
+	if !found {
+		let what = match expected {
+			Expected::Contains(needle) => format!("containing {needle:?}"),
+			Expected::Message(message) => format!("{message:?}"),
+		};
+		let level = level.map(|l| format!("{l} ")).unwrap_or_default();
+		panic!("no {level}event {what} was logged. Captured:\n{captured}");
+	}
+}
+
+/// Asserts the innermost [`capture`](crate::utils::tracing::test::capture) on this thread saw a matching event.
+///
+/// ```ignore
+/// assert_logged!(warn, contains = "Missing configuration field");
+/// assert_logged!(info, message = "Starting ...");
+/// assert_logged!(contains = "retrying"); // any level
+/// ```
+#[macro_export]
+macro_rules! assert_logged {
+	($level:ident, contains = $needle:expr $(,)?) => {
+		$crate::utils::tracing::test::__assert_logged(Some(stringify!($level)), $crate::utils::tracing::test::Expected::Contains($needle))
+	};
+	($level:ident, message = $message:expr $(,)?) => {
+		$crate::utils::tracing::test::__assert_logged(Some(stringify!($level)), $crate::utils::tracing::test::Expected::Message($message))
+	};
+	(contains = $needle:expr $(,)?) => {
+		$crate::utils::tracing::test::__assert_logged(None, $crate::utils::tracing::test::Expected::Contains($needle))
+	};
+	(message = $message:expr $(,)?) => {
+		$crate::utils::tracing::test::__assert_logged(None, $crate::utils::tracing::test::Expected::Message($message))
+	};
+}
+
+struct CaptureLayer {
+	events: Events,
+}
+
+/// Span fields, kept in the span's extensions.
+struct SpanFields(BTreeMap<String, String>);
+
+impl<S> Layer<S> for CaptureLayer
+where
+	S: Subscriber + for<'a> LookupSpan<'a>,
+{
+	fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+		let mut visitor = FieldVisitor::default();
+		attrs.record(&mut visitor);
+		if let Some(span) = ctx.span(id) {
+			span.extensions_mut().insert(SpanFields(visitor.fields));
+		}
+	}
+
+	fn on_record(&self, id: &span::Id, values: &span::Record<'_>, ctx: Context<'_, S>) {
+		let Some(span) = ctx.span(id) else { return };
+		let mut visitor = FieldVisitor::default();
+		values.record(&mut visitor);
+		if let Some(SpanFields(fields)) = span.extensions_mut().get_mut::<SpanFields>() {
+			fields.extend(visitor.fields);
+		}
+	}
+
+	fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+		let mut visitor = FieldVisitor::default();
+		event.record(&mut visitor);
+		let spans = ctx
+			.event_scope(event)
+			.map(|scope| {
+				scope
+					.from_root()
+					.map(|span| CapturedSpan {
+						name: span.name().to_owned(),
+						fields: span.extensions().get::<SpanFields>().map(|f| f.0.clone()).unwrap_or_default(),
+					})
+					.collect()
+			})
+			.unwrap_or_default();
+		self.events.lock().unwrap().push(CapturedEvent {
+			level: *event.metadata().level(),
+			target: event.metadata().target().to_owned(),
+			message: visitor.message,
+			fields: visitor.fields,
+			spans,
+		});
+	}
+}
+
+#[derive(Default)]
+struct FieldVisitor {
+	message: String,
+	fields: BTreeMap<String, String>,
+}
+
+impl Visit for FieldVisitor {
+	fn record_str(&mut self, field: &Field, value: &str) {
+		match field.name() {
+			"message" => self.message = value.to_owned(),
+			name => _ = self.fields.insert(name.to_owned(), value.to_owned()),
+		}
+	}
+
+	fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+		match field.name() {
+			"message" => self.message = format!("{value:?}"),
+			name => _ = self.fields.insert(name.to_owned(), format!("{value:?}")),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn captures_structured_events_in_span_context() {
+		let logs = capture();
+		tracing::info!("outside");
+		tracing::info_span!("order", id = 7).in_scope(|| {
+			tracing::warn!(venue = "binance", qty = 3, "Missing configuration field `fee`");
+		});
+
+		crate::assert_logged!(warn, contains = "Missing configuration field");
+		crate::assert_logged!(info, message = "outside");
+		let events = logs.events();
+		assert_eq!(events[1].fields.get("venue").map(String::as_str), Some("binance"));
+		insta::assert_snapshot!(logs.render(), @r"
+		 INFO v_utils::utils::tracing::test::tests: outside
+		 WARN order{id=7}: v_utils::utils::tracing::test::tests: Missing configuration field `fee` qty=3 venue=binance
+		");
+	}
+
+	#[test]
+	fn nested_capture_shadows_and_restores() {
+		let outer = capture();
+		{
+			let _inner = capture();
+			tracing::info!("inner");
+			crate::assert_logged!(contains = "inner");
+		}
+		tracing::info!("outer");
+		crate::assert_logged!(message = "outer");
+		assert_eq!(outer.events().len(), 1);
+	}
+
+	#[test]
+	#[should_panic(expected = "no ERROR event containing \"boom\" was logged")]
+	fn missing_event_panics_with_capture() {
+		let _logs = capture();
+		tracing::info!("boom");
+		crate::assert_logged!(error, contains = "boom");
+	}
+}
@@ -0,0 +1,113 @@
+//! `tokio-console`'s instrumentation layer, alongside the others.
+//!
+//! The console feeds on tokio's own `tokio=trace,runtime=trace` events and spans, so while it runs those directives
+//! are spliced into whatever the main ones are, and the instrumentation is kept out of the log file and OTLP export.
+//! Tokio only emits it when built with `--cfg tokio_unstable`.
+
+use std::borrow::Cow;
+#[cfg(feature = "tokio-console")]
+use std::sync::OnceLock;
+
+use tracing::{Level, Metadata, Subscriber};
+use tracing_subscriber::{
+	Layer,
+	filter::{FilterFn, filter_fn},
+	registry::LookupSpan,
+};
+
+use super::routing::is_within;
+
+const CONSOLE_DIRECTIVES: &str = "tokio=trace,runtime=trace";
+
+/// The console's gRPC server binds a fixed port, so a second instance in the same process would fail to start.
+#[cfg(feature = "tokio-console")]
+static SPAWNED: OnceLock<()> = OnceLock::new();
+
+/// Starts the console server, unless disabled or already started by an earlier init.
+#[cfg(feature = "tokio-console")]
+pub(super) fn layer<S>(destination: &super::LogDestination) -> Option<Box<dyn Layer<S> + Send + Sync>>
+where
+	S: Subscriber + for<'a> LookupSpan<'a> + Send + Sync, {
+	if !destination.tokio_console || SPAWNED.set(()).is_err() {
+		return None;
+	}
+	// Bind address, retention and the like come from the `TOKIO_CONSOLE_*` env.
+	Some(console_subscriber::ConsoleLayer::builder().with_default_env().spawn().boxed())
+}
+
+#[cfg(not(feature = "tokio-console"))]
+pub(super) fn layer<S>(_destination: &super::LogDestination) -> Option<Box<dyn Layer<S> + Send + Sync>>
+where
+	S: Subscriber + for<'a> LookupSpan<'a> + Send + Sync, {
+	None
+}
+
+fn is_running() -> bool {
+	#[cfg(feature = "tokio-console")]
+	return SPAWNED.get().is_some();
+	#[cfg(not(feature = "tokio-console"))]
+	false
+}
+
+/// `directives`, plus what the console needs if it's running. Appended, so they override the user's for `tokio` and
+/// `runtime` themselves, while more specific targets (e.g. `tokio::net=debug`) still win.
+pub(super) fn directives(directives: &str) -> Cow<'_, str> {
+	spliced(directives, is_running())
+}
+
+fn spliced(directives: &str, console: bool) -> Cow<'_, str> {
+	match console {
+		false => Cow::Borrowed(directives),
+		true if directives.trim().is_empty() => Cow::Borrowed(CONSOLE_DIRECTIVES),
+		true => Cow::Owned(format!("{directives},{CONSOLE_DIRECTIVES}")),
+	}
+}
+
+/// Wraps `layer` to not see the console's instrumentation, if the console is running.
+pub(super) fn without_instrumentation<L, S>(layer: L) -> Box<dyn Layer<S> + Send + Sync>
+where
+	L: Layer<S> + Send + Sync + 'static,
+	S: Subscriber + for<'a> LookupSpan<'a>, {
+	match is_running() {
+		true => layer.with_filter(outside_instrumentation()).boxed(),
+		false => layer.boxed(),
+	}
+}
+
+/// Everything but TRACE from `tokio` and `runtime`, so their debug and up still get through.
+fn outside_instrumentation() -> FilterFn<impl Fn(&Metadata<'_>) -> bool> {
+	filter_fn(|meta: &Metadata<'_>| *meta.level() != Level::TRACE || !(is_within(meta.target(), "tokio") || is_within(meta.target(), "runtime")))
+}
+
+#[cfg(test)]
+mod tests {
+	use tracing_subscriber::{EnvFilter, layer::SubscriberExt as _};
+
+	use super::*;
+	use crate::utils::tracing::{LogFormat, format::fmt_layer, tests::TestSink};
+
+	#[test]
+	fn splices_console_directives_only_when_running() {
+		assert_eq!(spliced("info", false), "info");
+		assert_eq!(spliced("", true), CONSOLE_DIRECTIVES);
+		assert_eq!(spliced("info,tokio=warn", true), "info,tokio=warn,tokio=trace,runtime=trace");
+	}
+
+	#[test]
+	fn instrumentation_stays_out_of_the_log() {
+		let sink = TestSink::default();
+		let text = fmt_layer(LogFormat::Text, Default::default(), sink.clone(), false, false).with_filter(outside_instrumentation());
+		let env_filter = EnvFilter::new(spliced("info,tokio=warn", true));
+
+		tracing::subscriber::with_default(tracing_subscriber::registry().with(env_filter).with(text), || {
+			tracing::trace!(target: "tokio::task::waker", "waker.wake");
+			tracing::trace!(target: "runtime::resource::poll_op", "poll_op");
+			tracing::debug!(target: "tokio::net", "bound");
+			tracing::info!("filled");
+		});
+
+		let out = sink.output();
+		assert_eq!(out.lines().count(), 2, "{out}");
+		assert!(out.contains("DEBUG tokio::net: bound") && out.contains("filled"), "{out}");
+	}
+}
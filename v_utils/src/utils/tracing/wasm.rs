@@ -0,0 +1,90 @@
+//! The browser flavour of [`init_subscriber`]: events go to the devtools console.
+
+use std::io;
+
+use tracing::{Level, Metadata, info};
+use tracing_error::ErrorLayer;
+use tracing_log::{AsLog as _, log};
+use tracing_subscriber::{fmt::MakeWriter, prelude::*};
+
+use super::{LogDestination, Redactor, filter_with_directives, live_directives, panic_hook};
+
+/// Sends tracing events to the browser console, through `console.error`/`warn`/`info`/`log`/`debug` by level.
+///
+/// Directives are the [`compiled_directives`](LogDestination::compiled_directives), or the defaults: there are no
+/// directives files to read in a browser. [`set_directives`](super::set_directives) and [`redact`](LogDestination::redact)
+/// work as on native; the options concerned with files, stderr, span timing, timestamps and OTLP don't apply.
+///
+/// Also installs `console_error_panic_hook`, so panics show up in the console with their message.
+pub fn init_subscriber(log_destination: LogDestination) {
+	console_error_panic_hook::set_once();
+
+	let mut logs_during_init: Vec<Box<dyn FnOnce()>> = Vec::new();
+	let (env_filter, filter_handle) = tracing_subscriber::reload::Layer::new(filter_with_directives(&mut logs_during_init, None, log_destination.compiled_directives));
+	let redactor = Redactor::new(&log_destination.redaction);
+	// The console timestamps entries itself, and `SystemTime::now` panics on wasm32-unknown-unknown anyway.
+	let console_layer = tracing_subscriber::fmt::layer().without_time().with_ansi(false).with_writer(redactor.writer(Console));
+
+	tracing_subscriber::registry()
+		.with(env_filter)
+		.with(redactor.layer())
+		.with(console_layer)
+		.with(ErrorLayer::default())
+		.init();
+	live_directives::register(filter_handle);
+	if log_destination.panic_hook {
+		panic_hook::install();
+	}
+
+	for log in logs_during_init {
+		log();
+	}
+	info!("Starting ...");
+}
+
+struct Console;
+
+impl<'a> MakeWriter<'a> for Console {
+	type Writer = ConsoleWriter;
+
+	fn make_writer(&'a self) -> Self::Writer {
+		ConsoleWriter {
+			level: Level::INFO,
+			buf: Vec::new(),
+		}
+	}
+
+	fn make_writer_for(&'a self, meta: &Metadata<'_>) -> Self::Writer {
+		ConsoleWriter {
+			level: *meta.level(),
+			buf: Vec::new(),
+		}
+	}
+}
+
+/// Collects one rendered event, and hands it to the console when dropped.
+struct ConsoleWriter {
+	level: Level,
+	buf: Vec<u8>,
+}
+
+impl io::Write for ConsoleWriter {
+	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+		self.buf.extend_from_slice(buf);
+		Ok(buf.len())
+	}
+
+	fn flush(&mut self) -> io::Result<()> {
+		Ok(())
+	}
+}
+
+impl Drop for ConsoleWriter {
+	fn drop(&mut self) {
+		if self.buf.is_empty() {
+			return;
+		}
+		let line = String::from_utf8_lossy(&self.buf);
+		console_log::log(&log::Record::builder().level(self.level.as_log()).args(format_args!("{}", line.trim_end())).build());
+	}
+}
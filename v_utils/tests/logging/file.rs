@@ -88,7 +88,7 @@ fn clientside_writes_stdout_stderr_and_file() {
 			// Thread ids
 			(r"ThreadId\(\d+\)", "ThreadId(N)"),
 			// Line numbers inside the library itself shift on every edit
-			(r"(src/utils/tracing\.rs):\d+", "$1:<LINE>"),
+			(r"(src/utils/tracing/\w+\.rs):\d+", "$1:<LINE>"),
 		],
 	}, {
 		insta::assert_snapshot!(combined);
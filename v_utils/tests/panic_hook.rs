@@ -0,0 +1,29 @@
+//! A test binary of its own: the panic hook is process-wide, so it would otherwise record the panics of every test
+//! running alongside.
+
+use v_utils::utils::{LogDestination, init_subscriber, tracing::test::capture};
+
+#[test]
+fn records_panic_with_location_thread_and_span_trace() {
+	let dir = tempfile::tempdir().unwrap();
+	init_subscriber(LogDestination::file(dir.path().join("app.log")).panic_hook(true));
+
+	let event = std::thread::Builder::new()
+		.name("order-worker".to_owned())
+		.spawn(|| {
+			let logs = capture();
+			let _ = std::panic::catch_unwind(|| tracing::info_span!("fill", id = 7).in_scope(|| panic!("boom {}", 7)));
+			logs.events().into_iter().find(|e| e.target == "panic").expect("the panic was recorded")
+		})
+		.unwrap()
+		.join()
+		.unwrap();
+
+	assert_eq!(event.level, tracing::Level::ERROR);
+	assert_eq!(event.message, "boom 7");
+	assert_eq!(event.fields["thread"], "order-worker");
+	assert!(event.fields["location"].contains("panic_hook.rs"), "{event}");
+	assert!(event.fields["span_trace"].contains("fill"), "{event}");
+	assert!(!event.fields["backtrace"].is_empty());
+	assert_eq!(event.spans[0].name, "fill");
+}
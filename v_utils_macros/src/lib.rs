@@ -26,27 +26,49 @@ use syn::{
 ///   Converts to named fields `{ source: T, backtrace, spantrace }`, adds `#[error("{source}")]`
 ///   if no `#[error(…)]` is already present, and generates a `From<T>` impl that captures
 ///   both backtrace and spantrace at the conversion site (`?`).
+///   With context next to the source (`Io { source: T, path: PathBuf }`), there is no `From<T>`;
+///   instead a `{Enum}{Variant}Context` trait gives `Result<_, T>` a `.with_path(…)` (fields joined
+///   by `_` for more of them), converting and capturing just the same.
 ///
 /// - **`#[own]`** — wraps one of our own typed errors that already carries backtrace/spantrace
 ///   (`Own(InnerError)` tuple variant). Adds `#[error(transparent)]` to the variant (if absent)
 ///   and `#[from]` + `#[backtrace]` to the inner field, so `thiserror`'s `provide()` delegates
 ///   to the source rather than capturing a new backtrace at the wrapping site.
 ///
+/// ## Exit codes and diagnostics (on variants, or on the struct)
+///
+/// - **`#[sysexit(Config)]`** — generates `v_utils::utils::SysexitCode`, mapping the variant to that
+///   `Sysexit`. `#[own]` variants without one take their source's, which then has to implement it too;
+///   other variants without one get `Sysexit::None`.
+/// - **`#[help("…")]`** — help text; `{field}` interpolates a named field.
+/// - **`#[code(my_app::bad_value)]`** — diagnostic code.
+///
+/// **`#[wrap_err(diagnostic)]`** also implements `miette::Diagnostic` (unless the type derives it already) from `#[help]`
+/// and `#[code]`. `#[own]` variants delegate to their source, which then has to implement it too, e.g. by being
+/// `#[wrap_err(diagnostic)]` itself. Needs `v_utils`' `miette` feature.
+///
 /// ## Struct usage
 ///
 /// Injects `backtrace`+`spantrace` into the named fields and generates `Self::new(…user_fields…)`.
+/// Tuple structs get them as the last two fields; unit structs turn into `{ backtrace, spantrace }`.
+///
+/// Generic error types work either way, with everything generated carrying over their parameters.
 ///
 /// ## Example
 /// ```ignore
 /// #[wrap_err]
 /// #[derive(Debug, thiserror::Error)]
+/// #[sysexit(Software)]
 /// #[error("something went wrong: {msg}")]
 /// pub struct MyLeafError { msg: String }
 ///
-/// #[wrap_err]
+/// #[wrap_err(diagnostic)]
 /// #[derive(Debug, thiserror::Error)]
 /// pub enum MyError {
 ///     #[leaf]
+///     #[sysexit(DataErr)]
+///     #[help("expected a number, got `{val}`")]
+///     #[code(my_app::bad_value)]
 ///     #[error("bad value: {val}")]
 ///     BadValue { val: String },
 ///
@@ -57,52 +79,111 @@ use syn::{
 ///     #[error("parse error: {source}")]
 ///     Parse(std::num::ParseIntError),
 ///
+///     // `std::fs::read(&path).with_path(&path)?`
+///     #[foreign]
+///     #[error("reading {path:?}: {source}")]
+///     Read { source: std::io::Error, path: std::path::PathBuf },
+///
 ///     #[own]
 ///     Inner(MyLeafError),
 /// }
 /// ```
 #[proc_macro_attribute]
-pub fn wrap_err(_attr: TokenStream, item: TokenStream) -> TokenStream {
+pub fn wrap_err(attr: TokenStream, item: TokenStream) -> TokenStream {
+	let mut diagnostic = false;
+	let options = syn::meta::parser(|meta| {
+		if meta.path.is_ident("diagnostic") {
+			diagnostic = true;
+			Ok(())
+		} else {
+			Err(meta.error("unsupported #[wrap_err] option, expected `diagnostic`"))
+		}
+	});
+	parse_macro_input!(attr with options);
 	let input = parse_macro_input!(item as syn::Item);
 
 	match input {
 		syn::Item::Struct(mut s) => {
 			let name = s.ident.clone();
 			let vis = s.vis.clone();
-
-			let user_fields: Vec<(syn::Ident, syn::Type)> = match &s.fields {
-				syn::Fields::Named(fields) => fields.named.iter().map(|f| (f.ident.clone().unwrap(), f.ty.clone())).collect(),
-				_ => panic!("#[wrap_err] on a struct requires named fields"),
+			let generics = s.generics.clone();
+			let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+			let attrs = match WrapErrAttrs::take(&mut s.attrs) {
+				Ok(attrs) => attrs,
+				Err(e) => return e.to_compile_error().into(),
 			};
+			let diagnostic = diagnostic && !derives_diagnostic(&s.attrs);
 
-			match &mut s.fields {
+			let (field_names, constructor) = match &mut s.fields {
 				syn::Fields::Named(fields) => {
+					let (names, types): (Vec<syn::Ident>, Vec<syn::Type>) = fields.named.iter().map(|f| (f.ident.clone().unwrap(), f.ty.clone())).unzip();
 					fields.named.push(syn::parse_quote! { backtrace: ::std::backtrace::Backtrace });
 					fields.named.push(syn::parse_quote! { spantrace: ::tracing_error::SpanTrace });
+					let constructor = quote! {
+						#vis fn new(#(#names: #types),*) -> Self {
+							Self {
+								#(#names,)*
+								backtrace: ::std::backtrace::Backtrace::capture(),
+								spantrace: ::tracing_error::SpanTrace::capture(),
+							}
+						}
+					};
+					(names, constructor)
 				}
-				_ => unreachable!(),
-			}
+				syn::Fields::Unnamed(fields) => {
+					let types: Vec<syn::Type> = fields.unnamed.iter().map(|f| f.ty.clone()).collect();
+					let names: Vec<syn::Ident> = (0..types.len()).map(|i| format_ident!("_{i}")).collect();
+					fields.unnamed.push(syn::parse_quote! { ::std::backtrace::Backtrace });
+					fields.unnamed.push(syn::parse_quote! { ::tracing_error::SpanTrace });
+					let constructor = quote! {
+						#vis fn new(#(#names: #types),*) -> Self {
+							Self(#(#names,)* ::std::backtrace::Backtrace::capture(), ::tracing_error::SpanTrace::capture())
+						}
+					};
+					(vec![], constructor)
+				}
+				syn::Fields::Unit => {
+					s.fields = syn::Fields::Named(syn::parse_quote! {{
+						backtrace: ::std::backtrace::Backtrace,
+						spantrace: ::tracing_error::SpanTrace
+					}});
+					s.semi_token = None;
+					let constructor = quote! {
+						#vis fn new() -> Self {
+							Self {
+								backtrace: ::std::backtrace::Backtrace::capture(),
+								spantrace: ::tracing_error::SpanTrace::capture(),
+							}
+						}
+					};
+					(vec![], constructor)
+				}
+			};
 
-			let param_names: Vec<&syn::Ident> = user_fields.iter().map(|(n, _)| n).collect();
-			let param_types: Vec<&syn::Type> = user_fields.iter().map(|(_, t)| t).collect();
+			let arms = vec![DiagnosticArm {
+				path: quote! { Self },
+				fields: field_names,
+				own: false,
+				attrs,
+			}];
+			let sysexit_impl = sysexit_impl(&name, &generics, &arms);
+			let diagnostic_impl = diagnostic_impl(&name, &generics, &arms, diagnostic);
 
 			quote! {
 				#s
-				impl #name {
-					#vis fn new(#(#param_names: #param_types),*) -> Self {
-						Self {
-							#(#param_names,)*
-							backtrace: ::std::backtrace::Backtrace::capture(),
-							spantrace: ::tracing_error::SpanTrace::capture(),
-						}
-					}
+				impl #impl_generics #name #ty_generics #where_clause {
+					#constructor
 				}
+				#sysexit_impl
+				#diagnostic_impl
 			}
 			.into()
 		}
 		syn::Item::Enum(mut e) => {
 			let name = e.ident.clone();
 			let vis = e.vis.clone();
+			let generics = e.generics.clone();
+			let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
 			struct LeafVariant {
 				ident: syn::Ident,
@@ -111,11 +192,19 @@ pub fn wrap_err(_attr: TokenStream, item: TokenStream) -> TokenStream {
 			struct ForeignVariant {
 				ident: syn::Ident,
 				inner_type: syn::Type,
+				/// Fields besides `source`, taken by the context trait's method
+				context: Vec<(syn::Ident, syn::Type)>,
 			}
 			let mut leaf_variants: Vec<LeafVariant> = Vec::new();
 			let mut foreign_variants: Vec<ForeignVariant> = Vec::new();
+			let mut arms: Vec<DiagnosticArm> = Vec::new();
+			let diagnostic = diagnostic && !derives_diagnostic(&e.attrs);
 
 			for variant in &mut e.variants {
+				let attrs = match WrapErrAttrs::take(&mut variant.attrs) {
+					Ok(attrs) => attrs,
+					Err(e) => return e.to_compile_error().into(),
+				};
 				let is_leaf = variant.attrs.iter().any(|a| a.path().is_ident("leaf"));
 				let is_foreign = variant.attrs.iter().any(|a| a.path().is_ident("foreign"));
 				let is_own = variant.attrs.iter().any(|a| a.path().is_ident("own"));
@@ -146,24 +235,40 @@ pub fn wrap_err(_attr: TokenStream, item: TokenStream) -> TokenStream {
 					}
 				} else if is_foreign {
 					variant.attrs.retain(|a| !a.path().is_ident("foreign"));
-					let inner_type = match &variant.fields {
-						syn::Fields::Unnamed(fields) if fields.unnamed.len() == 1 => fields.unnamed[0].ty.clone(),
-						_ => panic!("#[foreign] variants must be tuple variants with exactly one field, e.g. `Io(std::io::Error)`"),
+					let (inner_type, context) = match &mut variant.fields {
+						syn::Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+							let inner_type = fields.unnamed[0].ty.clone();
+							// Convert tuple field to named fields: { source: T, backtrace, spantrace }
+							variant.fields = syn::Fields::Named(syn::parse_quote! {{
+								source: #inner_type,
+								backtrace: ::std::backtrace::Backtrace,
+								spantrace: ::tracing_error::SpanTrace
+							}});
+							(inner_type, vec![])
+						}
+						syn::Fields::Named(fields) => {
+							let (sources, context): (Vec<&syn::Field>, Vec<&syn::Field>) = fields.named.iter().partition(|f| f.ident.as_ref().is_some_and(|i| i == "source"));
+							let Some(source) = sources.first() else {
+								panic!("#[foreign] variants with named fields must have a `source` field, e.g. `Io {{ source: std::io::Error, path: PathBuf }}`");
+							};
+							let inner_type = source.ty.clone();
+							let context = context.iter().map(|f| (f.ident.clone().unwrap(), f.ty.clone())).collect();
+							fields.named.push(syn::parse_quote! { backtrace: ::std::backtrace::Backtrace });
+							fields.named.push(syn::parse_quote! { spantrace: ::tracing_error::SpanTrace });
+							(inner_type, context)
+						}
+						_ =>
+							panic!("#[foreign] variants must be either `Io(std::io::Error)`, or have a `source` field next to context, e.g. `Io {{ source: std::io::Error, path: PathBuf }}`"),
 					};
 					foreign_variants.push(ForeignVariant {
 						ident: variant.ident.clone(),
-						inner_type: inner_type.clone(),
+						inner_type,
+						context,
 					});
 					// Add #[error("{source}")] only if no #[error(...)] attribute is present
 					if !variant.attrs.iter().any(|a| a.path().is_ident("error")) {
 						variant.attrs.push(syn::parse_quote! { #[error("{source}")] });
 					}
-					// Convert tuple field to named fields: { source: T, backtrace, spantrace }
-					variant.fields = syn::Fields::Named(syn::parse_quote! {{
-						source: #inner_type,
-						backtrace: ::std::backtrace::Backtrace,
-						spantrace: ::tracing_error::SpanTrace
-					}});
 				} else if is_own {
 					variant.attrs.retain(|a| !a.path().is_ident("own"));
 					match &mut variant.fields {
@@ -180,6 +285,14 @@ pub fn wrap_err(_attr: TokenStream, item: TokenStream) -> TokenStream {
 						_ => panic!("#[own] variants must be tuple variants with exactly one field, e.g. `Inner(InnerError)`"),
 					}
 				}
+
+				let variant_ident = &variant.ident;
+				arms.push(DiagnosticArm {
+					path: quote! { Self::#variant_ident },
+					fields: variant.fields.iter().filter_map(|f| f.ident.clone()).collect(),
+					own: is_own,
+					attrs,
+				});
 			}
 
 			let constructors = leaf_variants.iter().map(|lv| {
@@ -198,28 +311,61 @@ pub fn wrap_err(_attr: TokenStream, item: TokenStream) -> TokenStream {
 				}
 			});
 
+			// A `From` impl when the source is all there is; otherwise, a trait attaching the context to `Result`s.
 			let from_impls = foreign_variants.iter().map(|fv| {
 				let variant_ident = &fv.ident;
 				let inner_type = &fv.inner_type;
+				if fv.context.is_empty() {
+					return quote! {
+						impl #impl_generics From<#inner_type> for #name #ty_generics #where_clause {
+							fn from(source: #inner_type) -> Self {
+								Self::#variant_ident {
+									source,
+									backtrace: ::std::backtrace::Backtrace::capture(),
+									spantrace: ::tracing_error::SpanTrace::capture(),
+								}
+							}
+						}
+					};
+				}
+
+				let trait_ident = format_ident!("{name}{variant_ident}Context");
+				let method = format_ident!("with_{}", fv.context.iter().map(|(n, _)| n.to_string()).collect::<Vec<_>>().join("_"));
+				let param_names: Vec<&syn::Ident> = fv.context.iter().map(|(n, _)| n).collect();
+				let param_types: Vec<&syn::Type> = fv.context.iter().map(|(_, t)| t).collect();
+				let mut trait_generics = generics.clone();
+				trait_generics.params.push(syn::parse_quote! { __T });
+				let (trait_impl_generics, trait_ty_generics, _) = trait_generics.split_for_impl();
+				let doc = format!("`.{method}(..)` on `Result`s of the source of [`{name}::{variant_ident}`], converting into it");
 				quote! {
-					impl From<#inner_type> for #name {
-						fn from(source: #inner_type) -> Self {
-							Self::#variant_ident {
+					#[doc = #doc]
+					#vis trait #trait_ident #trait_impl_generics #where_clause {
+						fn #method(self, #(#param_names: impl ::std::convert::Into<#param_types>),*) -> ::std::result::Result<__T, #name #ty_generics>;
+					}
+					impl #trait_impl_generics #trait_ident #trait_ty_generics for ::std::result::Result<__T, #inner_type> #where_clause {
+						fn #method(self, #(#param_names: impl ::std::convert::Into<#param_types>),*) -> ::std::result::Result<__T, #name #ty_generics> {
+							self.map_err(|source| #name::#variant_ident {
 								source,
+								#(#param_names: #param_names.into(),)*
 								backtrace: ::std::backtrace::Backtrace::capture(),
 								spantrace: ::tracing_error::SpanTrace::capture(),
-							}
+							})
 						}
 					}
 				}
 			});
 
+			let sysexit_impl = sysexit_impl(&name, &generics, &arms);
+			let diagnostic_impl = diagnostic_impl(&name, &generics, &arms, diagnostic);
+
 			quote! {
 				#e
-				impl #name {
+				impl #impl_generics #name #ty_generics #where_clause {
 					#(#constructors)*
 				}
 				#(#from_impls)*
+				#sysexit_impl
+				#diagnostic_impl
 			}
 			.into()
 		}
@@ -232,8 +378,23 @@ pub fn wrap_err(_attr: TokenStream, item: TokenStream) -> TokenStream {
 #[proc_macro]
 pub fn graphemics(input: TokenStream) -> TokenStream {
 	let input = parse_macro_input!(input as syn::Ident);
+	let unique_items_valid = graphemics_of(&input.to_string());
+
+	let expanded = quote! {
+		{
+			let mut result: Vec<&'static str> = Vec::new();
+			#(
+			result.push(#unique_items_valid);
+		)*
+			result
+		}
+	};
+
+	TokenStream::from(expanded)
+}
 
-	let s = input.to_string();
+/// What [`graphemics!`] expands to for `s`, at macro expansion time.
+fn graphemics_of(s: &str) -> Vec<String> {
 	let mut split_caps = Vec::new();
 	let mut current_word = String::new();
 	for c in s.chars() {
@@ -251,7 +412,7 @@ pub fn graphemics(input: TokenStream) -> TokenStream {
 	let acronym_caps = acronym.to_uppercase();
 	let same_lower = s.to_lowercase();
 	let same_upper = s.to_uppercase();
-	let same = s.clone();
+	let same = s.to_owned();
 	let snake_case = split_caps.iter().map(|s| s.to_lowercase()).collect::<Vec<String>>().join("_");
 
 	let graphems = [acronym, acronym_caps, same_lower, same_upper, same, snake_case];
@@ -261,20 +422,28 @@ pub fn graphemics(input: TokenStream) -> TokenStream {
 			unique_items.push(item);
 		}
 	}
-	let unique_items_valid = unique_items.into_iter().filter(|s| s.len() != 1).collect::<Vec<String>>();
-
-	let expanded = quote! {
-		{
-			let mut result: Vec<&'static str> = Vec::new();
-			#(
-			result.push(#unique_items_valid);
-		)*
-			result
-		}
-	};
-
-	TokenStream::from(expanded)
+	unique_items.into_iter().filter(|s| s.len() != 1).collect::<Vec<String>>()
 }
+/// Derives `FromStr` and `Display` for the compact `name:xVALUE:yVALUE` format, e.g. `trailing_stop:p0.5:s42`.
+///
+/// The name is any of [`graphemics!`] of the type's, fields are keyed by their first character. On an enum, each
+/// variant (with named fields, or none) is such a format of its own, the variant's name acting as the prefix:
+/// `order:p{t=0.48;s=0.52}:c50` or `cancel:i7`.
+///
+/// Values with a `:` outside of braces, or unbalanced ones, are written in quotes, escaping `"` and `\` inside:
+/// `note:t"a:b"`. `Vec<T>` fields are `[a,b,c]`, and `Option<T>` ones are `None` when not provided.
+///
+/// - `#[compact(key = "tp")]` on a field: its key instead of the first character. Keys have to be unique and none a
+///   prefix of another, so fields with colliding first characters need keys of their own.
+/// - `#[compact(default)]` on a field: `Default` when it's not provided; `#[compact(default = expr)]` for a given
+///   value, as does an inline `field: T = expr`. On a struct: every field falls back to `Self::default()`'s.
+/// - `#[compact(serde)]`: also `Serialize`/`Deserialize` as the compact string, for configs and JSON payloads.
+/// - `#[compact(schema)]`: also `schemars::JsonSchema`, as a string with `compact_grammar()` as its `pattern`, so
+///   editors can check values inline. Needs `v_utils`' `schemars` feature.
+///
+/// Parse errors are `v_utils::utils::compact::CompactParseError`s, with the byte offset and what was expected there,
+/// into nested values too; with the `miette` feature they render with the input, underlined. `compact_grammar()`
+/// returns a regex of the format.
 #[proc_macro_derive(CompactFormatNamed, attributes(compact))]
 pub fn derive_compact_format_named(input: TokenStream) -> TokenStream {
 	// Pre-process: strip `= expr` default field values (rust nightly `default_field_values`),
@@ -283,179 +452,503 @@ pub fn derive_compact_format_named(input: TokenStream) -> TokenStream {
 
 	let ast = parse_macro_input!(cleaned_input as DeriveInput);
 	let name = &ast.ident;
-	let fields = if let Data::Struct(syn::DataStruct {
-		fields: Fields::Named(syn::FieldsNamed { ref named, .. }),
-		..
-	}) = ast.data
-	{
-		named
-	} else {
-		unimplemented!()
+	let container = match CompactAttrs::parse(&ast.attrs) {
+		Ok(attrs) => attrs,
+		Err(e) => return e.to_compile_error().into(),
 	};
 
-	// Check for struct-level #[compact(default)]
-	let struct_default = ast.attrs.iter().any(|attr| {
-		if let syn::Meta::List(meta_list) = &attr.meta {
-			if meta_list.path.is_ident("compact") {
-				return meta_list.tokens.to_string().trim() == "default";
+	let (parse, display, grammar) = match &ast.data {
+		Data::Struct(syn::DataStruct {
+			fields: Fields::Named(syn::FieldsNamed { named, .. }),
+			..
+		}) => {
+			let fields = match compact_fields(named, container.default, &inline_defaults) {
+				Ok(fields) => fields,
+				Err(e) => return e.to_compile_error().into(),
+			};
+			let (parse_fields, display_fields) = compact_named_fields(&quote! { #name }, &fields);
+			let idents = fields.iter().map(|f| &f.ident);
+			let parse = quote! {
+				let graphemics = v_utils::macros::graphemics!(#name);
+				if !graphemics.contains(&name) {
+					return Err(v_utils::utils::compact::CompactParseError::at(s, name, format!("one of {:?}", graphemics)));
+				}
+				#parse_fields
+			};
+			let grammar = compact_named_grammar(&quote! { #name }, &fields);
+			let display = quote! {
+				let graphemics = v_utils::macros::graphemics!(#name);
+				write!(f, "{}", graphemics[0])?;
+				let #name { #(#idents,)* } = self;
+				#display_fields
+			};
+			(parse, display, grammar)
+		}
+		Data::Enum(e) => {
+			if container.default {
+				panic!("#[compact(default)] is for structs; on an enum, put it on the fields of its variants");
 			}
+			// Which variant a name is for mustn't depend on declaration order.
+			let mut taken: Vec<(String, &syn::Ident)> = Vec::new();
+			for variant in &e.variants {
+				let names = graphemics_of(&variant.ident.to_string());
+				if let Some((name, other)) = taken.iter().find(|(taken, _)| names.contains(taken)) {
+					let msg = format!("`{name}` is a name of both `{other}` and `{}` (see `graphemics!`); rename one of them", variant.ident);
+					return syn::Error::new(variant.ident.span(), msg).to_compile_error().into();
+				}
+				taken.extend(names.into_iter().map(|name| (name, &variant.ident)));
+			}
+			let variants: Vec<(&syn::Ident, Vec<syn::Field>)> = e
+				.variants
+				.iter()
+				.map(|v| match &v.fields {
+					Fields::Named(fields) => (&v.ident, fields.named.iter().cloned().collect()),
+					Fields::Unit => (&v.ident, vec![]),
+					Fields::Unnamed(_) => panic!(
+						"CompactFormatNamed: variant `{}` must have named fields, or none; for wrapping a type deriving it, see TryParseVariants",
+						v.ident
+					),
+				})
+				.collect();
+
+			let mut parse_arms = Vec::new();
+			let mut display_arms = Vec::new();
+			let mut grammars = Vec::new();
+			for (variant, fields) in &variants {
+				let fields: syn::punctuated::Punctuated<syn::Field, Token![,]> = fields.iter().cloned().collect();
+				let fields = match compact_fields(&fields, false, &inline_defaults) {
+					Ok(fields) => fields,
+					Err(e) => return e.to_compile_error().into(),
+				};
+				let (parse_fields, display_fields) = compact_named_fields(&quote! { #name::#variant }, &fields);
+				let idents = fields.iter().map(|f| &f.ident);
+				grammars.push(compact_named_grammar(&quote! { #variant }, &fields));
+				parse_arms.push(quote! {
+					if v_utils::macros::graphemics!(#variant).contains(&name) {
+						return { #parse_fields };
+					}
+				});
+				display_arms.push(quote! {
+					#name::#variant { #(#idents,)* } => {
+						write!(f, "{}", v_utils::macros::graphemics!(#variant)[0])?;
+						#display_fields
+					}
+				});
+			}
+			let variant_idents = variants.iter().map(|(variant, _)| variant);
+			let parse = quote! {
+				#(#parse_arms)*
+				let expected: Vec<&str> = [#(v_utils::macros::graphemics!(#variant_idents)),*].concat();
+				Err(v_utils::utils::compact::CompactParseError::at(s, name, format!("one of {:?}", expected)))
+			};
+			let grammar = quote! { v_utils::utils::compact::grammar::alternatives([#(#grammars),*]) };
+			let display = quote! {
+				match self {
+					#(#display_arms)*
+				}
+			};
+			(parse, display, grammar)
 		}
-		false
-	});
-
-	let mut first_chars: Vec<char> = Vec::new();
-	for field in fields {
-		let first_char = field.ident.as_ref().unwrap().to_string().chars().next().unwrap();
-		if !first_chars.contains(&first_char) {
-			first_chars.push(first_char);
-		} else {
-			panic!("Field names must be unique");
+		_ => {
+			let msg = "CompactFormatNamed is for structs with named fields, and enums whose variants have named fields or none";
+			return syn::Error::new(name.span(), msg).to_compile_error().into();
 		}
-	}
-
-	let n_fields = fields.len();
+	};
 
-	// Parse per-field #[compact(default)] or #[compact(default = expr)] or inline `= expr`
-	let field_defaults: Vec<Option<proc_macro2::TokenStream>> = fields
-		.iter()
-		.map(|f| {
-			if struct_default {
-				let ident = &f.ident;
-				return Some(quote! { Self::default().#ident });
-			}
-			for attr in &f.attrs {
-				if let syn::Meta::List(meta_list) = &attr.meta {
-					if meta_list.path.is_ident("compact") {
-						let tokens_str = meta_list.tokens.to_string();
-						let trimmed = tokens_str.trim();
-						if trimmed == "default" {
-							let ty = &f.ty;
-							return Some(quote! { <#ty as Default>::default() });
-						}
-						if let Some(expr_str) = trimmed.strip_prefix("default =").or_else(|| trimmed.strip_prefix("default=")) {
-							let expr: proc_macro2::TokenStream = expr_str.trim().parse().expect("invalid expression in #[compact(default = ...)]");
-							return Some(expr);
-						}
-					}
+	let serde_impls = match container.serde {
+		true => quote! {
+			impl v_utils::__internal::serde::Serialize for #name {
+				fn serialize<S: v_utils::__internal::serde::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+					serializer.collect_str(self)
 				}
 			}
-			// Check for inline default field value (`field: Type = expr`)
-			let field_name = f.ident.as_ref().unwrap().to_string();
-			if let Some(expr_str) = inline_defaults.get(&field_name) {
-				let expr: proc_macro2::TokenStream = expr_str.parse().expect("invalid inline default expression");
-				return Some(expr);
-			}
-			None
-		})
-		.collect();
 
-	let map_fields_to_chars = fields.iter().zip(field_defaults.iter()).map(|(f, default)| {
-		let ident = &f.ident;
-		let ty = &f.ty;
-		let first_char = ident.as_ref().unwrap().to_string().chars().next().unwrap();
-		match default {
-			Some(fallback) => quote! {
-				#ident: match provided_params.get(&#first_char) {
-					Some(v) => v.parse::<#ty>()?,
-					None => #fallback,
-				},
-			},
-			None => {
-				let field_name = ident.as_ref().unwrap().to_string();
-				quote! {
-					#ident: match provided_params.get(&#first_char) {
-						Some(v) => v.parse::<#ty>()?,
-						None => v_utils::__internal::eyre::bail!("missing required field '{}'", #field_name),
-					},
+			impl<'de> v_utils::__internal::serde::Deserialize<'de> for #name {
+				fn deserialize<D: v_utils::__internal::serde::Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+					let s = <std::borrow::Cow<'de, str> as v_utils::__internal::serde::Deserialize>::deserialize(deserializer)?;
+					<Self as std::str::FromStr>::from_str(&s).map_err(v_utils::__internal::serde::de::Error::custom)
 				}
 			}
-		}
-	});
+		},
+		false => quote! {},
+	};
 
-	let display_fields = fields.iter().map(|f| {
-		let ident = &f.ident;
-		let first_char = ident.as_ref().unwrap().to_string().chars().next().unwrap();
-		quote! {
-			write!(f, ":{}{}", #first_char, self.#ident)?;
-		}
-	});
+	let schema_impl = match container.schema {
+		true => compact_schema_impl(name),
+		false => quote! {},
+	};
 
 	let expanded = quote! {
+		impl #name {
+			/// Regex of the strings `from_str` takes, unanchored; see `v_utils::utils::compact::grammar` for its limits.
+			pub fn compact_grammar() -> String {
+				#grammar
+			}
+		}
+
 		impl std::str::FromStr for #name {
-			type Err = v_utils::__internal::eyre::Report;
+			type Err = v_utils::utils::compact::CompactParseError;
 
-			fn from_str(s: &str) -> v_utils::__internal::eyre::Result<Self> {
+			fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
 				// Split on first ':' to separate name from params
 				let (name, params_part) = s.split_once(':').unwrap_or((s, ""));
+				#parse
+			}
+		}
 
-				// Brace-aware splitting: don't split on ':' inside {...}
-				fn split_respecting_braces(s: &str) -> Vec<&str> {
-					let mut result = Vec::new();
-					let mut depth = 0;
-					let mut start = 0;
-					for (i, c) in s.char_indices() {
-						match c {
-							'{' => depth += 1,
-							'}' => depth -= 1,
-							':' if depth == 0 => {
-								result.push(&s[start..i]);
-								start = i + 1;
-							}
-							_ => {}
-						}
-					}
-					if start < s.len() {
-						result.push(&s[start..]);
+		impl std::fmt::Display for #name {
+			fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+				#display
+				std::result::Result::Ok(())
+			}
+		}
+
+		#serde_impls
+		#schema_impl
+	};
+
+	expanded.into()
+}
+
+/// `#[compact(..)]` on the type deriving `CompactFormatNamed`
+#[derive(Default)]
+struct CompactAttrs {
+	default: bool,
+	serde: bool,
+	schema: bool,
+}
+
+impl CompactAttrs {
+	fn parse(attrs: &[syn::Attribute]) -> syn::Result<Self> {
+		let mut result = Self::default();
+		for attr in attrs.iter().filter(|a| a.path().is_ident("compact")) {
+			attr.parse_args_with(|input: ParseStream| {
+				while !input.is_empty() {
+					let ident: syn::Ident = input.parse()?;
+					if ident == "default" {
+						result.default = true;
+					} else if ident == "serde" {
+						result.serde = true;
+					} else if ident == "schema" {
+						result.schema = true;
+					} else {
+						return Err(unknown_attr_ident(&ident, &["default", "serde", "schema"]));
 					}
-					result
+					let _ = input.parse::<Option<Token![,]>>();
 				}
+				Ok(())
+			})?;
+		}
+		Ok(result)
+	}
+}
 
-				let params_split = if params_part == "" || params_part == "_" {
-					Vec::new()
-				} else {
-					split_respecting_braces(params_part)
-				};
+/// `#[compact(..)]` on a field: `default`, `default = expr`, `key = "tp"`
+#[derive(Default)]
+struct CompactFieldAttrs {
+	default: Option<Option<syn::Expr>>,
+	key: Option<syn::LitStr>,
+}
 
-				if params_split.len() > #n_fields {
-					v_utils::__internal::eyre::bail!("Expected at most {} fields, got {}", #n_fields, params_split.len());
+impl CompactFieldAttrs {
+	fn parse(attrs: &[syn::Attribute]) -> syn::Result<Self> {
+		let mut result = Self::default();
+		for attr in attrs.iter().filter(|a| a.path().is_ident("compact")) {
+			attr.parse_args_with(|input: ParseStream| {
+				while !input.is_empty() {
+					let ident: syn::Ident = input.parse()?;
+					if ident == "default" {
+						result.default = Some(match input.parse::<Option<Token![=]>>()? {
+							Some(_) => Some(input.parse()?),
+							None => None,
+						});
+					} else if ident == "key" {
+						input.parse::<Token![=]>()?;
+						let key: syn::LitStr = input.parse()?;
+						if key.value().is_empty() || key.value().contains([':', ';', '=', ',', '"', '{', '}', '[', ']']) {
+							return Err(syn::Error::new(key.span(), "a compact key must be non-empty, without separators, quotes or brackets"));
+						}
+						result.key = Some(key);
+					} else {
+						return Err(unknown_attr_ident(&ident, &["default", "key"]));
+					}
+					let _ = input.parse::<Option<Token![,]>>();
 				}
-				let graphemics = v_utils::macros::graphemics!(#name);
-				if !graphemics.contains(&name) {
-					v_utils::__internal::eyre::bail!("Incorrect name provided. Expected one of: {:?}", graphemics);
+				Ok(())
+			})?;
+		}
+		Ok(result)
+	}
+}
+
+/// A field of a `CompactFormatNamed`, with its key and the fallback for when it's not provided.
+struct CompactField {
+	ident: syn::Ident,
+	ty: syn::Type,
+	key: String,
+	default: Option<proc_macro2::TokenStream>,
+}
+
+/// How a field's value is written: `Vec`s as `[a,b,c]`, `Option`s by being there or not, the rest through
+/// `FromStr`/`Display`.
+enum CompactKind<'a> {
+	Plain(&'a syn::Type),
+	List(Box<CompactKind<'a>>),
+	Optional(Box<CompactKind<'a>>),
+}
+
+impl<'a> CompactKind<'a> {
+	fn of(ty: &'a syn::Type) -> Self {
+		match (generic_inner(ty, "Option"), generic_inner(ty, "Vec")) {
+			(Some(inner), _) => Self::Optional(Box::new(Self::of_item(inner))),
+			(None, Some(inner)) => Self::List(Box::new(Self::of_item(inner))),
+			(None, None) => Self::Plain(ty),
+		}
+	}
+
+	/// Within a value, absence can't be told apart, so an `Option` is just a type that has to implement `FromStr`.
+	fn of_item(ty: &'a syn::Type) -> Self {
+		match generic_inner(ty, "Vec") {
+			Some(inner) => Self::List(Box::new(Self::of_item(inner))),
+			None => Self::Plain(ty),
+		}
+	}
+
+	/// Expression parsing the `&str` `value` into a `Result` of the field's type.
+	fn parse(&self, value: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+		match self {
+			Self::Plain(ty) => quote! { v_utils::utils::compact::parse_value::<#ty>(s, #value, stringify!(#ty)) },
+			Self::List(item) => {
+				let parse_item = item.parse(quote! { item });
+				quote! {
+					v_utils::utils::compact::parse_list(s, #value)
+						.and_then(|items| items.into_iter().map(|item| #parse_item).collect::<std::result::Result<Vec<_>, _>>())
 				}
+			}
+			Self::Optional(inner) => {
+				let parse_inner = inner.parse(value);
+				quote! { #parse_inner.map(Some) }
+			}
+		}
+	}
 
-				let mut provided_params: std::collections::HashMap<char, &str> = std::collections::HashMap::new();
-				for param in params_split {
-					if let Some(first_char) = param.chars().next() {
-						let value = &param[1..];
-						provided_params.insert(first_char, value);
-					}
+	/// Expression of the regex of its values, where `sep` separates them.
+	fn grammar(&self, sep: char) -> proc_macro2::TokenStream {
+		match self {
+			Self::Plain(ty) => {
+				let ident = match ty {
+					syn::Type::Path(type_path) => type_path.path.segments.last().map(|s| s.ident.to_string()).unwrap_or_default(),
+					_ => String::new(),
+				};
+				match ident.as_str() {
+					"i8" | "i16" | "i32" | "i64" | "i128" | "isize" => quote! { v_utils::utils::compact::grammar::INT.to_owned() },
+					"u8" | "u16" | "u32" | "u64" | "u128" | "usize" => quote! { v_utils::utils::compact::grammar::UINT.to_owned() },
+					"f32" | "f64" => quote! { v_utils::utils::compact::grammar::FLOAT.to_owned() },
+					"bool" => quote! { v_utils::utils::compact::grammar::BOOL.to_owned() },
+					_ => quote! { v_utils::utils::compact::grammar::value(#sep) },
 				}
-				Ok(#name {
-					#(#map_fields_to_chars)*
+			}
+			Self::List(item) => {
+				let item = item.grammar(',');
+				quote! { v_utils::utils::compact::grammar::list(&#item) }
+			}
+			Self::Optional(inner) => inner.grammar(sep),
+		}
+	}
+
+	/// Expression rendering the reference `value` into a `String`, before any quoting. Not for `Optional`.
+	fn display(&self, value: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+		match self {
+			Self::Plain(_) => quote! { #value.to_string() },
+			Self::List(item) => {
+				let display_item = item.display(quote! { item });
+				quote! { v_utils::utils::compact::list(#value.iter().map(|item| #display_item)) }
+			}
+			Self::Optional(inner) => inner.display(value),
+		}
+	}
+}
+
+/// `T` of `ty` being `wrapper<T>`, by the last path segment.
+fn generic_inner<'a>(ty: &'a syn::Type, wrapper: &str) -> Option<&'a syn::Type> {
+	let syn::Type::Path(type_path) = ty else { return None };
+	let segment = type_path.path.segments.last()?;
+	if segment.ident != wrapper {
+		return None;
+	}
+	match &segment.arguments {
+		syn::PathArguments::AngleBracketed(args) if args.args.len() == 1 => match args.args.first() {
+			Some(syn::GenericArgument::Type(inner)) => Some(inner),
+			_ => None,
+		},
+		_ => None,
+	}
+}
+
+/// Keys and fallbacks of one set of named fields, from `#[compact(..)]`s and inline `= expr`s.
+///
+/// # Panics
+/// If two keys are the same, or one is a prefix of another, as either would make params ambiguous.
+fn compact_fields(
+	fields: &syn::punctuated::Punctuated<syn::Field, Token![,]>,
+	struct_default: bool,
+	inline_defaults: &std::collections::HashMap<String, String>,
+) -> syn::Result<Vec<CompactField>> {
+	let mut result: Vec<CompactField> = Vec::with_capacity(fields.len());
+	for f in fields {
+		let ident = f.ident.clone().unwrap();
+		let attrs = CompactFieldAttrs::parse(&f.attrs)?;
+		let ty = &f.ty;
+		let default = if struct_default {
+			Some(quote! { Self::default().#ident })
+		} else if let Some(default) = attrs.default {
+			Some(match default {
+				Some(expr) => quote! { #expr },
+				None => quote! { <#ty as Default>::default() },
 			})
+		} else {
+			inline_defaults
+				.get(&ident.to_string())
+				.map(|expr_str| expr_str.parse().expect("invalid inline default expression"))
+		};
+		let key = match attrs.key {
+			Some(key) => key.value(),
+			None => ident.to_string().chars().next().unwrap().to_string(),
+		};
+
+		for other in &result {
+			if other.key == key {
+				panic!(
+					"Field names must be unique: `{}` and `{ident}` are both keyed `{key}`; give one its own with #[compact(key = \"..\")]",
+					other.ident
+				);
 			}
+			let (shorter, longer) = match (key.starts_with(&other.key), other.key.starts_with(&key)) {
+				(true, _) => ((&other.ident, &other.key), (&ident, &key)),
+				(_, true) => ((&ident, &key), (&other.ident, &other.key)),
+				_ => continue,
+			};
+			panic!(
+				"Field keys must not be prefixes of each other: `{}`'s `{}` is one of `{}`'s `{}`",
+				shorter.0, shorter.1, longer.0, longer.1
+			);
 		}
+		result.push(CompactField {
+			ident,
+			ty: ty.clone(),
+			key,
+			default,
+		});
+	}
+	Ok(result)
+}
 
-		impl std::fmt::Display for #name {
-			fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-				let graphemics = v_utils::macros::graphemics!(#name);
-				let a_struct_name = graphemics[0];
-				write!(f, "{}", a_struct_name)?;
+/// For one set of named fields (a struct's, or a variant's): the expression parsing `params_part` into `path { .. }`,
+/// and the statements writing the fields, bound by name, after the name.
+fn compact_named_fields(path: &proc_macro2::TokenStream, fields: &[CompactField]) -> (proc_macro2::TokenStream, proc_macro2::TokenStream) {
+	let keys = fields.iter().map(|f| &f.key);
+
+	let field_inits = fields.iter().map(|f| {
+		let CompactField { ident, ty, key, default } = f;
+		let kind = CompactKind::of(ty);
+		let value = kind.parse(quote! { v });
+		let fallback = match (default, &kind) {
+			(Some(fallback), _) => fallback.clone(),
+			(None, CompactKind::Optional(_)) => quote! { None },
+			(None, _) => {
+				let expected = format!("the required field '{ident}' (key `{key}`)");
+				quote! { return Err(v_utils::utils::compact::CompactParseError::missing(s, #expected)) }
+			}
+		};
+		quote! {
+			#ident: match provided_params.get(#key) {
+				Some(v) => #value?,
+				None => #fallback,
+			},
+		}
+	});
 
-				#(#display_fields)*
+	let parse = quote! {
+		const KEYS: &[&str] = &[#(#keys),*];
+		let params_split = if params_part == "" || params_part == "_" {
+			Vec::new()
+		} else {
+			v_utils::utils::compact::split(params_part, ':')
+		};
 
-				std::result::Result::Ok(())
+		#[allow(unused_mut)]
+		let mut provided_params: std::collections::HashMap<&str, &str> = std::collections::HashMap::new();
+		for param in params_split {
+			let (key, value) = v_utils::utils::compact::split_key(param, KEYS)
+				.ok_or_else(|| v_utils::utils::compact::CompactParseError::at(s, param, format!("a field keyed by any of {:?}", KEYS)))?;
+			if provided_params.insert(key, value).is_some() {
+				return Err(v_utils::utils::compact::CompactParseError::at(s, &param[..key.len()], "each field at most once"));
 			}
 		}
+		Ok(#path {
+			#(#field_inits)*
+		})
 	};
 
-	expanded.into()
+	let display = fields.iter().map(|f| {
+		let CompactField { ident, ty, key, .. } = f;
+		let kind = CompactKind::of(ty);
+		match &kind {
+			CompactKind::Optional(_) => {
+				let value = kind.display(quote! { v });
+				quote! {
+					if let Some(v) = #ident {
+						write!(f, ":{}{}", #key, v_utils::utils::compact::quote(&#value, ':'))?;
+					}
+				}
+			}
+			_ => {
+				let value = kind.display(quote! { #ident });
+				quote! {
+					write!(f, ":{}{}", #key, v_utils::utils::compact::quote(&#value, ':'))?;
+				}
+			}
+		}
+	});
+
+	(parse, quote! { #(#display)* })
+}
+/// Expression of the regex of one set of named fields, after any of `graphemics!(ident)`.
+fn compact_named_grammar(ident: &proc_macro2::TokenStream, fields: &[CompactField]) -> proc_macro2::TokenStream {
+	let keys = fields.iter().map(|f| &f.key);
+	let values = fields.iter().map(|f| CompactKind::of(&f.ty).grammar(':'));
+	quote! {
+		v_utils::utils::compact::grammar::named(&v_utils::macros::graphemics!(#ident), &[#((#keys, #values)),*])
+	}
 }
-/// Derives `FromStr` for an enum where every variant is a single-field tuple variant.
+
+/// `JsonSchema` of the compact string: a `string`, with `compact_grammar()` as its `pattern`.
+fn compact_schema_impl(name: &syn::Ident) -> proc_macro2::TokenStream {
+	let name_str = name.to_string();
+	quote! {
+		impl v_utils::__internal::schemars::JsonSchema for #name {
+			fn schema_name() -> std::borrow::Cow<'static, str> {
+				#name_str.into()
+			}
+
+			fn json_schema(_: &mut v_utils::__internal::schemars::SchemaGenerator) -> v_utils::__internal::schemars::Schema {
+				v_utils::__internal::schemars::json_schema!({
+					"type": "string",
+					"pattern": format!("^(?:{})$", Self::compact_grammar()),
+				})
+			}
+		}
+	}
+}
+/// Derives `FromStr` for an enum of single-field tuple variants and unit variants.
 ///
-/// For each variant `Foo(Bar)`, tries `s.parse::<Bar>()` and wraps the result
-/// in `Self::Foo(v)`. Returns `Err(s.to_string())` if no variant matches.
+/// For each variant `Foo(Bar)`, tries `s.parse::<Bar>()` and wraps the result in `Self::Foo(v)`; a unit variant `Baz`
+/// takes any of [`graphemics!`] of its name. Variants are tried in order, the first to parse wins. If none does, the
+/// error is a `v_utils::utils::compact::TryParseError` listing why not for each.
+///
+/// - `#[try_parse(prefix = "foo")]` on a variant: input starting with `foo` goes to that variant alone, and input that
+///   doesn't never does.
+/// - `#[try_parse(strict)]` on the enum: every variant is tried, and input more than one of them takes is an error.
 ///
 /// Pairs well with `CompactFormatNamed` — if each inner type derives `CompactFormatNamed`,
 /// the enum automatically parses any of them by name.
@@ -472,6 +965,7 @@ pub fn derive_compact_format_named(input: TokenStream) -> TokenStream {
 /// pub enum MyEnum {
 ///     Foo(Foo),
 ///     Bar(Bar),
+///     Idle,
 /// }
 ///
 /// let parsed: MyEnum = "foo:x42".parse().unwrap();
@@ -480,45 +974,169 @@ pub fn derive_compact_format_named(input: TokenStream) -> TokenStream {
 /// let parsed: MyEnum = "bar:y3.14".parse().unwrap();
 /// assert_eq!(parsed, MyEnum::Bar(Bar { y: 3.14 }));
 ///
+/// assert_eq!("idle".parse::<MyEnum>().unwrap(), MyEnum::Idle);
 /// assert!("unknown".parse::<MyEnum>().is_err());
 /// ```
-#[proc_macro_derive(TryParseVariants)]
+#[proc_macro_derive(TryParseVariants, attributes(try_parse))]
 pub fn derive_try_parse_variants(input: TokenStream) -> TokenStream {
 	let ast = parse_macro_input!(input as DeriveInput);
 	let name = &ast.ident;
+	let name_str = name.to_string();
 
 	let variants = match &ast.data {
 		Data::Enum(e) => &e.variants,
 		_ => panic!("TryParseVariants can only be derived on enums"),
 	};
+	let strict = match TryParseAttrs::parse(&ast.attrs) {
+		Ok(TryParseAttrs { prefix: Some(prefix), .. }) => return syn::Error::new(prefix.span(), "`prefix` goes on variants").to_compile_error().into(),
+		Ok(attrs) => attrs.strict,
+		Err(e) => return e.to_compile_error().into(),
+	};
 
-	let arms = variants.iter().map(|v| {
+	let mut prefixed: Vec<(String, proc_macro2::TokenStream)> = Vec::new();
+	let mut unprefixed = Vec::new();
+	for v in variants {
 		let variant_ident = &v.ident;
-		let inner_ty = match &v.fields {
-			Fields::Unnamed(fields) if fields.unnamed.len() == 1 => &fields.unnamed.first().unwrap().ty,
-			_ => panic!("TryParseVariants: variant `{variant_ident}` must be a single-field tuple variant"),
+		let variant_str = variant_ident.to_string();
+		let prefix = match TryParseAttrs::parse(&v.attrs) {
+			Ok(TryParseAttrs { strict: true, .. }) => {
+				return syn::Error::new(variant_ident.span(), "`strict` goes on the enum").to_compile_error().into();
+			}
+			Ok(attrs) => attrs.prefix,
+			Err(e) => return e.to_compile_error().into(),
+		};
+		let attempt = match &v.fields {
+			Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+				let inner_ty = &fields.unnamed.first().unwrap().ty;
+				quote! { s.parse::<#inner_ty>().map(#name::#variant_ident).map_err(|e| e.to_string()) }
+			}
+			Fields::Unit => {
+				if let Some(prefix) = &prefix {
+					return syn::Error::new(prefix.span(), "unit variants are matched by name, not by prefix").to_compile_error().into();
+				}
+				quote! {
+					match v_utils::macros::graphemics!(#variant_ident) {
+						names if names.contains(&s) => Ok(#name::#variant_ident),
+						names => Err(format!("not one of {:?}", names)),
+					}
+				}
+			}
+			_ => panic!("TryParseVariants: variant `{variant_ident}` must be a single-field tuple variant, or a unit one"),
 		};
 
-		quote! {
-			if let Ok(v) = s.parse::<#inner_ty>() {
-				return Ok(#name::#variant_ident(v));
+		match prefix {
+			Some(prefix) => {
+				let prefix = prefix.value();
+				if let Some((other, _)) = prefixed.iter().find(|(other, _)| *other == prefix) {
+					panic!("TryParseVariants: prefix `{other}` is on more than one variant");
+				}
+				prefixed.push((
+					prefix.clone(),
+					quote! {
+						if s.starts_with(#prefix) {
+							return #attempt.map_err(|reason| v_utils::utils::compact::TryParseError::NoMatch {
+								ty: #name_str,
+								input: s.to_owned(),
+								attempts: vec![(#variant_str, reason)],
+							});
+						}
+						attempts.push((#variant_str, format!("doesn't start with `{}`", #prefix)));
+					},
+				));
 			}
+			None => unprefixed.push(match strict {
+				true => quote! {
+					match #attempt {
+						Ok(v) => parsed.push((#variant_str, v)),
+						Err(reason) => attempts.push((#variant_str, reason)),
+					}
+				},
+				false => quote! {
+					match #attempt {
+						Ok(v) => return Ok(v),
+						Err(reason) => attempts.push((#variant_str, reason)),
+					}
+				},
+			}),
 		}
-	});
+	}
+	// Longest first, so that of `foo` and `foobar`, input starting with `foobar` goes to the latter.
+	prefixed.sort_by_key(|(prefix, _)| std::cmp::Reverse(prefix.len()));
+	let prefixed = prefixed.into_iter().map(|(_, arm)| arm);
+
+	let no_match = quote! {
+		v_utils::utils::compact::TryParseError::NoMatch {
+			ty: #name_str,
+			input: s.to_owned(),
+			attempts,
+		}
+	};
+	let (parsed, outcome) = match strict {
+		true => (
+			quote! { let mut parsed: Vec<(&'static str, Self)> = Vec::new(); },
+			quote! {
+				match parsed.len() {
+					0 => Err(#no_match),
+					1 => Ok(parsed.pop().unwrap().1),
+					_ => Err(v_utils::utils::compact::TryParseError::Ambiguous {
+						ty: #name_str,
+						input: s.to_owned(),
+						variants: parsed.into_iter().map(|(variant, _)| variant).collect(),
+					}),
+				}
+			},
+		),
+		false => (quote! {}, quote! { Err(#no_match) }),
+	};
 
 	let expanded = quote! {
 		impl std::str::FromStr for #name {
-			type Err = String;
+			type Err = v_utils::utils::compact::TryParseError;
 
 			fn from_str(s: &str) -> Result<Self, Self::Err> {
-				#(#arms)*
-				Err(s.to_string())
+				#[allow(unused_mut)]
+				let mut attempts: Vec<(&'static str, String)> = Vec::new();
+				#parsed
+				#(#prefixed)*
+				#(#unprefixed)*
+				#outcome
 			}
 		}
 	};
 
 	expanded.into()
 }
+
+/// `#[try_parse(..)]`: `strict` on the enum, `prefix = "foo"` on variants
+#[derive(Default)]
+struct TryParseAttrs {
+	strict: bool,
+	prefix: Option<syn::LitStr>,
+}
+
+impl TryParseAttrs {
+	fn parse(attrs: &[syn::Attribute]) -> syn::Result<Self> {
+		let mut result = Self::default();
+		for attr in attrs.iter().filter(|a| a.path().is_ident("try_parse")) {
+			attr.parse_args_with(|input: ParseStream| {
+				while !input.is_empty() {
+					let ident: syn::Ident = input.parse()?;
+					if ident == "strict" {
+						result.strict = true;
+					} else if ident == "prefix" {
+						input.parse::<Token![=]>()?;
+						result.prefix = Some(input.parse()?);
+					} else {
+						return Err(unknown_attr_ident(&ident, &["strict", "prefix"]));
+					}
+					let _ = input.parse::<Option<Token![,]>>();
+				}
+				Ok(())
+			})?;
+		}
+		Ok(result)
+	}
+}
 /// Deprecated alias for [`CompactFormatNamed`]
 #[deprecated(since = "3.0.0", note = "Use CompactFormatNamed instead")]
 #[proc_macro_derive(CompactFormat)]
@@ -536,6 +1154,8 @@ pub fn derive_compact_format(input: TokenStream) -> TokenStream {
 /// - Key-value pairs separated by `=`
 /// - Pairs delimited by `;`
 ///
+/// Errors and `compact_grammar()` are as for [`CompactFormatNamed`].
+///
 /// # Example
 /// ```rust
 /// # use v_utils_macros::CompactFormatMap;
@@ -600,61 +1220,65 @@ pub fn derive_compact_format_map(input: TokenStream) -> TokenStream {
 		}
 	}
 
-	let n_fields = fields.len();
+	let keys: Vec<String> = first_chars.iter().map(char::to_string).collect();
 
-	let map_fields_to_chars = fields.iter().map(|f| {
+	let map_fields_to_chars = fields.iter().zip(&keys).map(|(f, key)| {
 		let ident = &f.ident;
 		let ty = &f.ty;
-		let first_char = ident.as_ref().unwrap().to_string().chars().next().unwrap();
+		let expected = format!("the field '{}' (key `{key}`)", ident.as_ref().unwrap());
 		quote! {
-			#ident: provided_params.get(&#first_char)
-				.ok_or_else(|| v_utils::__internal::eyre::eyre!("Missing field '{}' (key '{}')", stringify!(#ident), #first_char))?
-				.parse::<#ty>()?,
+			#ident: match provided_params.get(#key) {
+				Some(v) => v_utils::utils::compact::parse_value::<#ty>(s, v, stringify!(#ty))?,
+				None => return Err(v_utils::utils::compact::CompactParseError::new(s, s.len() - 1, 0, #expected)),
+			},
 		}
 	});
+	let grammar_fields = fields.iter().zip(&keys).map(|(f, key)| {
+		let value = CompactKind::Plain(&f.ty).grammar(';');
+		quote! { (#key, #value) }
+	});
 
 	let display_fields = fields.iter().enumerate().map(|(i, f)| {
 		let ident = &f.ident;
 		let first_char = ident.as_ref().unwrap().to_string().chars().next().unwrap();
 		if i == 0 {
 			quote! {
-				write!(f, "{}={}", #first_char, self.#ident)?;
+				write!(f, "{}={}", #first_char, v_utils::utils::compact::quote(&self.#ident.to_string(), ';'))?;
 			}
 		} else {
 			quote! {
-				write!(f, ";{}={}", #first_char, self.#ident)?;
+				write!(f, ";{}={}", #first_char, v_utils::utils::compact::quote(&self.#ident.to_string(), ';'))?;
 			}
 		}
 	});
 
 	let expanded = quote! {
+		impl #name {
+			/// Regex of the strings `from_str` takes, unanchored; see `v_utils::utils::compact::grammar` for its limits.
+			pub fn compact_grammar() -> String {
+				v_utils::utils::compact::grammar::map(&[#(#grammar_fields),*])
+			}
+		}
+
 		impl std::str::FromStr for #name {
-			type Err = v_utils::__internal::eyre::Report;
+			type Err = v_utils::utils::compact::CompactParseError;
 
-			fn from_str(s: &str) -> v_utils::__internal::eyre::Result<Self> {
-				// Strip outer braces if present
-				let inner = s.strip_prefix('{')
+			fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+				const KEYS: &[&str] = &[#(#keys),*];
+				let inner = s
+					.strip_prefix('{')
 					.and_then(|s| s.strip_suffix('}'))
-					.ok_or_else(|| v_utils::__internal::eyre::eyre!("CompactFormatMap must be wrapped in {{...}}, got: {}", s))?;
-
-				if inner.is_empty() && #n_fields > 0 {
-					v_utils::__internal::eyre::bail!("Expected {} fields, got empty map", #n_fields);
-				}
-
-				let pairs: Vec<&str> = if inner.is_empty() { Vec::new() } else { inner.split(';').collect() };
-				if pairs.len() != #n_fields {
-					v_utils::__internal::eyre::bail!("Expected {} fields, got {}", #n_fields, pairs.len());
-				}
-
-				let mut provided_params: std::collections::HashMap<char, &str> = std::collections::HashMap::new();
-				for pair in pairs {
-					let (key, value) = pair.split_once('=')
-						.ok_or_else(|| v_utils::__internal::eyre::eyre!("Invalid key=value pair: {}", pair))?;
-					if let Some(first_char) = key.chars().next() {
-						if key.len() != 1 {
-							v_utils::__internal::eyre::bail!("Key must be a single character, got: {}", key);
-						}
-						provided_params.insert(first_char, value);
+					.ok_or_else(|| v_utils::utils::compact::CompactParseError::at(s, s, "a map in `{...}`"))?;
+
+				#[allow(unused_mut)]
+				let mut provided_params: std::collections::HashMap<&str, &str> = std::collections::HashMap::new();
+				for pair in v_utils::utils::compact::split(inner, ';') {
+					let (key, value) = pair
+						.split_once('=')
+						.filter(|(key, _)| KEYS.contains(key))
+						.ok_or_else(|| v_utils::utils::compact::CompactParseError::at(s, pair, format!("`key=value`, with a key of {:?}", KEYS)))?;
+					if provided_params.insert(key, value).is_some() {
+						return Err(v_utils::utils::compact::CompactParseError::at(s, key, "each field at most once"));
 					}
 				}
 
@@ -676,13 +1300,28 @@ pub fn derive_compact_format_map(input: TokenStream) -> TokenStream {
 
 	expanded.into()
 }
-/// Put on a struct with optional fields, each of which implements FromStr
+/// Put on a struct with optional fields, each of which implements FromStr (with a `Display` error). Implements
+/// `TryFrom<Vec<impl AsRef<str>>>`, filling fields from the tokens.
+///
+/// A token is either
+/// - `key=value`, with `key` a field's name: `value` is parsed into that field. Given again, the later one wins.
+/// - anything else: goes to the first field left whose type parses it. So same-typed fields fill in declaration order.
+///
+/// `key=value` tokens go first, so a bare token never takes a field that's named elsewhere.
+///
+/// Fails with a `v_utils::utils::compact::FromVecStrError` listing every token that didn't fit: ones no field left
+/// parses, ones that parse as fields left of different types, and `key=value`s that don't parse.
+///
+/// `#[from_vec_str(strict)]` on the struct also rejects a field being given more than once, by key or bare: a bare
+/// token that only parses as fields already filled counts as a repeat of the first of them.
 ///
-///BUG: may write to the wrong field, if any of the child structs share the same acronym AND same fields. In reality, shouldn't happen.
-#[proc_macro_derive(OptionalFieldsFromVecStr)]
+/// # Panics
+/// On fields that aren't `Option`s.
+#[proc_macro_derive(OptionalFieldsFromVecStr, attributes(from_vec_str))]
 pub fn derive_optional_fields_from_vec_str(input: TokenStream) -> TokenStream {
 	let ast = parse_macro_input!(input as DeriveInput);
 	let name = &ast.ident;
+	let name_str = name.to_string();
 	let fields = if let Data::Struct(syn::DataStruct {
 		fields: Fields::Named(syn::FieldsNamed { ref named, .. }),
 		..
@@ -692,67 +1331,185 @@ pub fn derive_optional_fields_from_vec_str(input: TokenStream) -> TokenStream {
 	} else {
 		unimplemented!()
 	};
+	let strict = match FromVecStrAttrs::parse(&ast.attrs) {
+		Ok(attrs) => attrs.strict,
+		Err(e) => return e.to_compile_error().into(),
+	};
 
-	let init_nones = fields.iter().map(|f| {
-		let ident = &f.ident;
-		let ty = &f.ty;
-		quote! {
-			let mut #ident: #ty = None;
-		}
-	});
+	let idents: Vec<_> = fields.iter().map(|f| f.ident.as_ref().unwrap()).collect();
+	let names: Vec<String> = idents.iter().map(|ident| ident.to_string()).collect();
+	let inner_types: Vec<&syn::Type> = fields
+		.iter()
+		.map(|f| generic_inner(&f.ty, "Option").unwrap_or_else(|| panic!("All fields must be of type Option<T>")))
+		.collect();
+	// fields of the same type are one group: a bare token parsing as several fields of it is positional, not ambiguous
+	let groups: Vec<usize> = inner_types
+		.iter()
+		.map(|ty| inner_types.iter().position(|other| quote!(#other).to_string() == quote!(#ty).to_string()).unwrap())
+		.collect();
+	let indices = 0..fields.len();
+	let candidates: Vec<Ident> = idents.iter().map(|ident| format_ident!("{ident}_candidate")).collect();
 
-	let write_fields = fields.iter().map(|f| {
-		let ident = &f.ident;
+	let keyed_arms = idents.iter().zip(&names).zip(&inner_types).enumerate().map(|(i, ((ident, name), ty))| {
 		quote! {
-			#ident,
+			#name => match value.parse::<#ty>() {
+				Ok(parsed) => {
+					#ident = Some(parsed);
+					given[#i].push(token.to_owned());
+				}
+				Err(e) => issues.push(v_utils::utils::compact::FromVecStrIssue::Invalid {
+					token: token.to_owned(),
+					field: #name,
+					reason: e.to_string(),
+				}),
+			},
 		}
 	});
 
-	let conversions = fields.iter().map(|field| {
-		let field_name = &field.ident;
-		let field_type = match &field.ty {
-			syn::Type::Path(type_path) if type_path.path.segments.last().unwrap().ident == "Option" => {
-				let generic_arg = match type_path.path.segments.last().unwrap().arguments {
-					syn::PathArguments::AngleBracketed(ref args) => &args.args[0],
-					_ => panic!("Expected generic argument for Option"),
+	let try_candidates = idents
+		.iter()
+		.zip(&inner_types)
+		.zip(&candidates)
+		.zip(&groups)
+		.enumerate()
+		.map(|(i, (((ident, ty), candidate), group))| {
+			let filled_arm = match strict {
+				true => quote! {
+					Some(_) => {
+						if token.parse::<#ty>().is_ok() {
+							filled.push(#i);
+						}
+						None
+					}
+				},
+				false => quote! { Some(_) => None, },
+			};
+			quote! {
+				let #candidate = match #ident {
+					#filled_arm
+					None => token.parse::<#ty>().ok(),
 				};
-				quote! { #generic_arg }
+				if #candidate.is_some() {
+					fits.push((#i, #group));
+				}
 			}
-			_ => panic!("All fields must be of type Option<T>"),
-		};
+		});
 
-		quote! {
-			if #field_name.is_none() {
-				if let std::result::Result::Ok(value) = s.as_ref().parse::<#field_type>() {
-					#field_name = core::option::Option::Some(value);
-					continue;
+	let filled = match strict {
+		true => quote! {
+			// fields already filled that parse the token, to tell repeats
+			let mut filled: Vec<usize> = Vec::new();
+		},
+		false => quote! {},
+	};
+	let unmatched = match strict {
+		true => quote! {
+			None => match filled.first() {
+				Some(&i) => given[i].push(token.to_owned()),
+				None => issues.push(v_utils::utils::compact::FromVecStrIssue::Unmatched(token.to_owned())),
+			},
+		},
+		false => quote! {
+			None => issues.push(v_utils::utils::compact::FromVecStrIssue::Unmatched(token.to_owned())),
+		},
+	};
+	let duplicates = match strict {
+		true => quote! {
+			for (&field, tokens) in FIELDS.iter().zip(given) {
+				if tokens.len() > 1 {
+					issues.push(v_utils::utils::compact::FromVecStrIssue::Duplicate { field, tokens });
 				}
 			}
-		}
-	});
+		},
+		false => quote! {
+			let _ = given;
+		},
+	};
 
 	let expanded = quote! {
-	impl<S: AsRef<str>> TryFrom<Vec<S>> for #name {
-		type Error = &'static str;
-
-		fn try_from(strings: Vec<S>) -> core::result::Result<Self, Self::Error> {
-			#(#init_nones)*
-
-			for s in strings {
-				#(#conversions)*
+		impl<S: AsRef<str>> TryFrom<Vec<S>> for #name {
+			type Error = v_utils::utils::compact::FromVecStrError;
+
+			fn try_from(strings: Vec<S>) -> core::result::Result<Self, Self::Error> {
+				const FIELDS: &[&str] = &[#(#names),*];
+				#(let mut #idents = None;)*
+				let mut issues = Vec::new();
+				// tokens each field was given, to tell duplicates
+				let mut given: Vec<Vec<String>> = vec![Vec::new(); FIELDS.len()];
+
+				let mut bare = Vec::new();
+				for token in &strings {
+					let token = token.as_ref();
+					match token.split_once('=').filter(|(key, _)| FIELDS.contains(key)) {
+						Some((key, value)) => match key {
+							#(#keyed_arms)*
+							_ => unreachable!(),
+						},
+						None => bare.push(token),
+					}
+				}
 
-				return std::result::Result::Err("Could not parse string");
-			}
+				for token in bare {
+					// (field, group) of every field left that parses the token
+					let mut fits: Vec<(usize, usize)> = Vec::new();
+					#filled
+					#(#try_candidates)*
+					match fits.first() {
+						#unmatched
+						Some((_, group)) if fits.iter().any(|(_, g)| g != group) => issues.push(v_utils::utils::compact::FromVecStrIssue::Ambiguous {
+							token: token.to_owned(),
+							fields: fits.iter().map(|(i, _)| FIELDS[*i]).collect(),
+						}),
+						Some((i, _)) => {
+							given[*i].push(token.to_owned());
+							match i {
+								#(#indices => #idents = #candidates,)*
+								_ => unreachable!(),
+							}
+						}
+					}
+				}
+				#duplicates
 
+				if !issues.is_empty() {
+					return Err(v_utils::utils::compact::FromVecStrError { ty: #name_str, issues });
+				}
 				std::result::Result::Ok(#name {
-					#(#write_fields)*
-			})
+					#(#idents,)*
+				})
 			}
 		}
 	};
 
 	expanded.into()
 }
+
+/// `#[from_vec_str(..)]` on the type deriving `OptionalFieldsFromVecStr`: `strict`
+#[derive(Default)]
+struct FromVecStrAttrs {
+	strict: bool,
+}
+
+impl FromVecStrAttrs {
+	fn parse(attrs: &[syn::Attribute]) -> syn::Result<Self> {
+		let mut result = Self::default();
+		for attr in attrs.iter().filter(|a| a.path().is_ident("from_vec_str")) {
+			attr.parse_args_with(|input: ParseStream| {
+				while !input.is_empty() {
+					let ident: syn::Ident = input.parse()?;
+					if ident == "strict" {
+						result.strict = true;
+					} else {
+						return Err(unknown_attr_ident(&ident, &["strict"]));
+					}
+					let _ = input.parse::<Option<Token![,]>>();
+				}
+				Ok(())
+			})?;
+		}
+		Ok(result)
+	}
+}
 #[proc_macro_derive(VecFieldsFromVecStr)]
 pub fn derive_optioinal_vec_fields_from_vec_str(input: TokenStream) -> TokenStream {
 	let ast = parse_macro_input!(input as syn::DeriveInput);
@@ -1409,6 +2166,32 @@ pub fn derive_config_json_schema(input: TokenStream) -> TokenStream {
 	.into()
 }
 
+/// Builds a polars `DataFrame` out of JSON rows: `Vec<serde_json::Value>`, or anything else iterating over them.
+///
+/// Each `(at, dtype, name)` is a column, taking from every row the value
+/// - at index `at` of a row that's an array, like Binance's klines
+/// - at dotted path `"at"` of a row that's an object, like `"k.c"`, or `"bids.0.price"` with indices into arrays
+///
+/// `dtype` is any of `i64`, `i32`, `u64`, `u32`, `f64`, `f32`, `bool`, `String`, or `Datetime` for ms timestamps. Numbers
+/// and bools are also taken string-encoded, as `"91528.00000000"`. A column that can be missing or `null` is
+/// `Option<dtype>`; anywhere else, that, or a value that isn't a `dtype`, is an `Err` naming the row.
+///
+/// Returns `polars::prelude::PolarsResult<DataFrame>`. Requires `polars` in scope, with `dtype-datetime` for `Datetime`.
+///
+/// # Example
+/// ```rust,ignore
+/// let df = make_df![
+/// 	klines =>
+/// 	(0, Datetime, open_time)
+/// 	(4, f64, close)
+/// ]?;
+/// let df = make_df![
+/// 	trades =>
+/// 	("T", Datetime, time)
+/// 	("p", f64, price)
+/// 	("m.maker", Option<bool>, is_maker)
+/// ]?;
+/// ```
 #[proc_macro]
 pub fn make_df(input: TokenStream) -> TokenStream {
 	let DataFrameDef { values_vec, fields, .. } = parse_macro_input!(input as DataFrameDef);
@@ -1418,69 +2201,182 @@ pub fn make_df(input: TokenStream) -> TokenStream {
 		syn::Ident::new(&vec_name, name.span())
 	}
 
-	let vec_declarations = fields.iter().map(|field| {
+	let mut columns = Vec::with_capacity(fields.len());
+	for field in &fields {
+		match DfColumnType::of_make_df(&field.dtype) {
+			Ok(column) => columns.push(column),
+			Err(e) => return e.to_compile_error().into(),
+		}
+	}
+
+	let vec_declarations = fields.iter().zip(&columns).map(|(field, column)| {
 		let vec_ident = vec_name(&field.name);
-		let ty = &field.dtype;
+		let ty = column.rust_ty();
 		quote! {
 			let mut #vec_ident: Vec<#ty> = Vec::new();
 		}
 	});
 
-	// Generate tuple of indices for pattern matching
-	let indices = fields.iter().map(|field| {
-		let idx = &field.index;
+	let push_statements = fields.iter().zip(&columns).map(|(field, column)| {
+		let name_str = field.name.to_string();
+		let vec_name = vec_name(&field.name);
+		let get = match &field.at {
+			DfAt::Index(idx) => quote! { value.get(#idx) },
+			DfAt::Path(path) => {
+				// `serde_json::Value::pointer`, with `~` and `/` within keys escaped
+				let pointer = path.value().split('.').map(|key| format!("/{}", key.replace('~', "~0").replace('/', "~1"))).collect::<String>();
+				quote! { value.pointer(#pointer) }
+			}
+		};
+		let at = match &field.at {
+			DfAt::Index(idx) => idx.to_string(),
+			DfAt::Path(path) => format!("{:?}", path.value()),
+		};
+		let parse = column.parse_json(quote! { v });
+		let expected = column.scalar.to_string();
+		let parsed = quote! {
+			#parse.ok_or_else(|| polars::prelude::PolarsError::ComputeError(format!("row {}: `{}` is {}, expected {}", row, #name_str, v, #expected).into()))?
+		};
+		let on_missing = match column.nullable {
+			true => quote! { None },
+			false => quote! { return Err(polars::prelude::PolarsError::ComputeError(format!("row {}: nothing at {} for `{}`", row, #at, #name_str).into())) },
+		};
+		let parsed = match column.nullable {
+			true => quote! { Some(#parsed) },
+			false => parsed,
+		};
+		quote! {
+			#vec_name.push(match #get.filter(|v| !v.is_null()) {
+				Some(v) => #parsed,
+				None => #on_missing,
+			});
+		}
+	});
+
+	let df_columns = fields.iter().zip(&columns).map(|(field, column)| {
+		let vec_name = vec_name(&field.name);
+		let name_str = field.name.to_string();
+		let cast = column.cast();
 		quote! {
-			value.get(#idx)
+			polars::prelude::Column::new(#name_str.into(), #vec_name)#cast
 		}
 	});
 
-	// Generate push statements with appropriate type conversion
-	let push_statements = fields.iter().map(|field| {
-		let name = &field.name;
-		let vec_name = vec_name(name);
-		let dtype = &field.dtype;
+	quote! {
+		(|| -> polars::prelude::PolarsResult<polars::prelude::DataFrame> {
+			#(#vec_declarations)*
+
+			let mut height = 0;
+			for (row, value) in std::iter::IntoIterator::into_iter(#values_vec).enumerate() {
+				#(#push_statements)*
+				height = row + 1;
+			}
+
+			polars::prelude::DataFrame::new(height, vec![#(#df_columns),*])
+		})()
+	}
+	.into()
+}
+
+/// Implements conversions between a struct's `Vec` and a polars `DataFrame`, a column per field.
+///
+/// Generates
+/// - `fn to_df(rows: &[Self]) -> PolarsResult<DataFrame>`
+/// - `fn from_df(df: &DataFrame) -> PolarsResult<Vec<Self>>`, finding columns by field name, erroring on ones that are
+///   missing, of another dtype, or with a `null` for a field that isn't an `Option`
+///
+/// Fields are any of `i64`, `i32`, `u64`, `u32`, `f64`, `f32`, `bool` and `String`, or `Option`s of them. An `i64` (or
+/// `Option<i64>`) of ms timestamps marked `#[df(datetime)]` is a `Datetime` column.
+///
+/// Requires `polars` in scope, with `dtype-datetime` for `#[df(datetime)]`.
+///
+/// # Example
+/// ```rust,ignore
+/// #[derive(Clone, Debug, IntoDataFrame)]
+/// struct Kline {
+/// 	#[df(datetime)]
+/// 	open_time: i64,
+/// 	close: f64,
+/// 	trades: Option<u64>,
+/// }
+///
+/// let df = Kline::to_df(&klines)?;
+/// assert_eq!(Kline::from_df(&df)?, klines);
+/// ```
+#[proc_macro_derive(IntoDataFrame, attributes(df))]
+pub fn into_data_frame(input: TokenStream) -> TokenStream {
+	let input = parse_macro_input!(input as DeriveInput);
+	let name = &input.ident;
+	let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+	let fields = match &input.data {
+		Data::Struct(data) => match &data.fields {
+			Fields::Named(fields) => &fields.named,
+			_ => panic!("#[derive(IntoDataFrame)] can only be used on structs with named fields"),
+		},
+		_ => panic!("#[derive(IntoDataFrame)] can only be used on structs"),
+	};
 
-		// might not cover all the polars as methods correctly, may need to be updated to an explicit match statement through them.
-		let as_method = syn::Ident::new(&format!("as_{dtype}"), dtype.span());
+	let mut columns = Vec::with_capacity(fields.len());
+	for f in fields {
+		match DfColumnType::of_field(f) {
+			Ok(column) => columns.push(column),
+			Err(e) => return e.to_compile_error().into(),
+		}
+	}
 
+	let to_columns = fields.iter().zip(&columns).map(|(f, column)| {
+		let ident = f.ident.as_ref().unwrap();
+		let name_str = ident.to_string();
+		let ty = column.rust_ty();
+		let cast = column.cast();
 		quote! {
-			// inefficient but that's data-analysis, don't think I care
-			// unwrap_or would be preferrable as we expect this line to be taken ~50% of the time, but it breaks expected type (maybe it's still possible - try again later)
-			#vec_name.push(#name.#as_method().unwrap_or_else(|| #name.as_str().unwrap().parse::<#dtype>().unwrap()));
+			polars::prelude::Column::new(#name_str.into(), rows.iter().map(|row| row.#ident.clone()).collect::<Vec<#ty>>())#cast
 		}
 	});
 
-	let df_fields = fields.iter().map(|field| {
-		let vec_name = vec_name(&field.name);
-		let name_str = &field.name.to_string();
-		quote! {
-			#name_str => #vec_name
+	let chunked = fields.iter().zip(&columns).map(|(f, column)| {
+		let ident = f.ident.as_ref().unwrap();
+		let name_str = ident.to_string();
+		let accessor = column.scalar.accessor();
+		match column.scalar {
+			DfScalar::Datetime => quote! {
+				let #ident = df.column(#name_str)?.cast(&polars::prelude::DataType::Int64)?;
+				let #ident = #ident.#accessor()?;
+			},
+			_ => quote! {
+				let #ident = df.column(#name_str)?.#accessor()?;
+			},
 		}
 	});
 
-	// pretty sure there is a better way to do this, but eh
-	let temp_vars = fields
-		.iter()
-		.map(|field| {
-			let name = format!("{}", field.name);
-			syn::Ident::new(&name, proc_macro2::Span::call_site())
-		})
-		.collect::<Vec<_>>();
+	let field_inits = fields.iter().zip(&columns).map(|(f, column)| {
+		let ident = f.ident.as_ref().unwrap();
+		let name_str = ident.to_string();
+		let value = match column.scalar {
+			DfScalar::String => quote! { #ident.get(i).map(str::to_owned) },
+			_ => quote! { #ident.get(i) },
+		};
+		match column.nullable {
+			true => quote! { #ident: #value, },
+			false => quote! {
+				#ident: #value.ok_or_else(|| polars::prelude::PolarsError::ComputeError(format!("row {}: `{}` is null", i, #name_str).into()))?,
+			},
+		}
+	});
 
 	quote! {
-	{
-		#(#vec_declarations)*
-
-		for value in #values_vec {
-			if let (#(Some(#temp_vars)),*) = (#(#indices),*) {
-				#(#push_statements;)*
-			}
+		impl #impl_generics #name #ty_generics #where_clause {
+			/// A column per field, a row per element.
+			pub fn to_df(rows: &[Self]) -> polars::prelude::PolarsResult<polars::prelude::DataFrame> {
+				polars::prelude::DataFrame::new(rows.len(), vec![#(#to_columns),*])
 			}
 
-			let df = polars::df![
-			#(#df_fields),*
-		].expect("Failed to create DataFrame");
-			df
+			/// An element per row, fields from the columns of the same name.
+			pub fn from_df(df: &polars::prelude::DataFrame) -> polars::prelude::PolarsResult<Vec<Self>> {
+				#(#chunked)*
+				(0..df.height()).map(|i| Ok(Self { #(#field_inits)* })).collect()
+			}
 		}
 	}
 	.into()
@@ -1508,6 +2404,48 @@ pub fn wrap_new(input: TokenStream) -> TokenStream {
 
 	TokenStream::from(expanded)
 }
+/// Implements `Display`, `FromStr` and serde for an enum of unit variants, each through one name.
+///
+/// Names are the variants' idents in the enum's case, kebab-case unless `#[str_enum(case = "..")]` says otherwise; one of
+/// `kebab`, `snake`, `screaming`, `screaming-kebab`, `camel`, `pascal`, `lower` and `upper`. Parsing ignores ASCII case,
+/// and also takes any of a variant's aliases. Also gives `Enum::ALL`, every variant in declaration order, and `as_str()`.
+///
+/// # Attributes
+/// On the enum:
+/// - `#[str_enum(case = "snake")]`
+/// - `#[str_enum(value_enum)]` also implements `clap::ValueEnum` (requires `clap` in scope and the enum being `Clone`)
+///   clap matches the names and aliases itself, ignoring case only with `#[arg(ignore_case = true)]`
+/// - `#[str_enum(schema)]` also implements `JsonSchema`, as a string `enum` of the names and aliases
+///
+/// On a variant:
+/// - `#[str_enum(rename = "..")]` gives it a name as-is, instead of its ident in the enum's case
+/// - `#[str_enum(alias = "..")]` or `#[str_enum(alias = ["..", ".."])]` adds strings it's parsed from
+///
+/// Strings that are the same ignoring case, among all names and aliases, are a compile error.
+///
+/// # Example
+/// ```rust,ignore
+/// #[derive(Clone, Copy, Debug, PartialEq, StrEnum)]
+/// #[str_enum(case = "snake", value_enum)]
+/// enum Side {
+/// 	Buy,
+/// 	#[str_enum(alias = ["short", "s"])]
+/// 	Sell,
+/// }
+///
+/// assert_eq!("SHORT".parse::<Side>().unwrap(), Side::Sell);
+/// assert_eq!(Side::Sell.to_string(), "sell");
+/// assert_eq!(Side::ALL, &[Side::Buy, Side::Sell]);
+/// ```
+#[proc_macro_derive(StrEnum, attributes(str_enum))]
+pub fn str_enum(input: TokenStream) -> TokenStream {
+	let input = parse_macro_input!(input as DeriveInput);
+	match str_enum_impl(&input) {
+		Ok(expanded) => expanded.into(),
+		Err(e) => e.to_compile_error().into(),
+	}
+}
+
 /// Implements Display and FromStr for variants of an enum, using SCREAMING_SNAKE_CASE
 #[proc_macro_derive(ScreamIt)]
 pub fn scream_it(input: TokenStream) -> TokenStream {
@@ -1598,6 +2536,263 @@ pub fn scream_it(input: TokenStream) -> TokenStream {
 
 	TokenStream::from(expanded)
 }
+
+/// `#[str_enum(case = "..")]`
+#[derive(Clone, Copy)]
+enum StrCase {
+	Kebab,
+	Snake,
+	Screaming,
+	ScreamingKebab,
+	Camel,
+	Pascal,
+	Lower,
+	Upper,
+}
+
+impl StrCase {
+	const NAMES: [&str; 8] = ["kebab", "snake", "screaming", "screaming-kebab", "camel", "pascal", "lower", "upper"];
+
+	fn parse(lit: &syn::LitStr) -> syn::Result<Self> {
+		Ok(match lit.value().as_str() {
+			"kebab" => Self::Kebab,
+			"snake" => Self::Snake,
+			"screaming" => Self::Screaming,
+			"screaming-kebab" => Self::ScreamingKebab,
+			"camel" => Self::Camel,
+			"pascal" => Self::Pascal,
+			"lower" => Self::Lower,
+			"upper" => Self::Upper,
+			other => return Err(syn::Error::new(lit.span(), format!("unknown case `{other}`; valid values are: {}", Self::NAMES.join(", ")))),
+		})
+	}
+
+	fn apply(self, ident: &str) -> String {
+		match self {
+			Self::Kebab => heck::AsKebabCase(ident).to_string(),
+			Self::Snake => AsSnakeCase(ident).to_string(),
+			Self::Screaming => AsShoutySnakeCase(ident).to_string(),
+			Self::ScreamingKebab => heck::AsShoutyKebabCase(ident).to_string(),
+			Self::Camel => heck::AsLowerCamelCase(ident).to_string(),
+			Self::Pascal => heck::AsUpperCamelCase(ident).to_string(),
+			Self::Lower => ident.to_lowercase(),
+			Self::Upper => ident.to_uppercase(),
+		}
+	}
+}
+
+/// `#[str_enum(..)]` on the enum: `case = ".."`, `value_enum`, `schema`
+#[derive(Default)]
+struct StrEnumAttrs {
+	case: Option<StrCase>,
+	value_enum: bool,
+	schema: bool,
+}
+
+impl StrEnumAttrs {
+	fn parse(attrs: &[syn::Attribute]) -> syn::Result<Self> {
+		let mut result = Self::default();
+		for attr in attrs.iter().filter(|a| a.path().is_ident("str_enum")) {
+			attr.parse_args_with(|input: ParseStream| {
+				while !input.is_empty() {
+					let ident: syn::Ident = input.parse()?;
+					if ident == "case" {
+						input.parse::<Token![=]>()?;
+						result.case = Some(StrCase::parse(&input.parse()?)?);
+					} else if ident == "value_enum" {
+						result.value_enum = true;
+					} else if ident == "schema" {
+						result.schema = true;
+					} else {
+						return Err(unknown_attr_ident(&ident, &["case", "value_enum", "schema"]));
+					}
+					let _ = input.parse::<Option<Token![,]>>();
+				}
+				Ok(())
+			})?;
+		}
+		Ok(result)
+	}
+}
+
+/// `#[str_enum(..)]` on a variant: `rename = ".."`, `alias = ".."`, `alias = ["..", ..]`
+#[derive(Default)]
+struct StrVariantAttrs {
+	rename: Option<syn::LitStr>,
+	aliases: Vec<syn::LitStr>,
+}
+
+impl StrVariantAttrs {
+	fn parse(attrs: &[syn::Attribute]) -> syn::Result<Self> {
+		let mut result = Self::default();
+		for attr in attrs.iter().filter(|a| a.path().is_ident("str_enum")) {
+			attr.parse_args_with(|input: ParseStream| {
+				while !input.is_empty() {
+					let ident: syn::Ident = input.parse()?;
+					if ident == "rename" {
+						input.parse::<Token![=]>()?;
+						result.rename = Some(input.parse()?);
+					} else if ident == "alias" {
+						input.parse::<Token![=]>()?;
+						if input.peek(token::Bracket) {
+							let content;
+							syn::bracketed!(content in input);
+							result.aliases.extend(content.parse_terminated(<syn::LitStr as Parse>::parse, Token![,])?);
+						} else {
+							result.aliases.push(input.parse()?);
+						}
+					} else {
+						return Err(unknown_attr_ident(&ident, &["rename", "alias"]));
+					}
+					let _ = input.parse::<Option<Token![,]>>();
+				}
+				Ok(())
+			})?;
+		}
+		Ok(result)
+	}
+}
+
+fn str_enum_impl(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+	let name = &input.ident;
+	let name_str = name.to_string();
+
+	let variants = if let Data::Enum(syn::DataEnum { variants, .. }) = &input.data {
+		variants
+	} else {
+		panic!("#[derive(StrEnum)] can only be used on enums");
+	};
+	let container = StrEnumAttrs::parse(&input.attrs)?;
+	let case = container.case.unwrap_or(StrCase::Kebab);
+
+	let mut idents = Vec::with_capacity(variants.len());
+	let mut names = Vec::with_capacity(variants.len());
+	let mut aliases = Vec::with_capacity(variants.len());
+	// every string parsed from, lowercased, with the variant it's for
+	let mut taken: Vec<(String, &syn::Ident)> = Vec::new();
+	for variant in variants {
+		if !matches!(variant.fields, Fields::Unit) {
+			return Err(syn::Error::new(variant.ident.span(), "#[derive(StrEnum)] only takes unit variants"));
+		}
+		let attrs = StrVariantAttrs::parse(&variant.attrs)?;
+		let variant_name = match &attrs.rename {
+			Some(rename) => rename.value(),
+			None => case.apply(&variant.ident.to_string()),
+		};
+		let strings = std::iter::once((variant_name.clone(), variant.ident.span())).chain(attrs.aliases.iter().map(|a| (a.value(), a.span())));
+		for (s, span) in strings {
+			let lowercase = s.to_ascii_lowercase();
+			if let Some((_, other)) = taken.iter().find(|(t, _)| *t == lowercase) {
+				let msg = match *other == &variant.ident {
+					true => format!("`{s}` is given twice for `{other}`; parsing ignores case"),
+					false => format!("`{s}` would parse as both `{other}` and `{}`; parsing ignores case", variant.ident),
+				};
+				return Err(syn::Error::new(span, msg));
+			}
+			taken.push((lowercase, &variant.ident));
+		}
+
+		idents.push(&variant.ident);
+		names.push(variant_name);
+		aliases.push(attrs.aliases.iter().map(|a| a.value()).collect::<Vec<_>>());
+	}
+
+	let parse_arms = idents.iter().zip(&names).zip(&aliases).map(|((ident, name), aliases)| {
+		let lowercase = std::iter::once(name).chain(aliases).map(|s| s.to_ascii_lowercase());
+		quote! { #(#lowercase)|* => Ok(Self::#ident), }
+	});
+	let expected = names.join(", ");
+
+	let value_enum_impl = match container.value_enum {
+		true => {
+			let possible_values = idents.iter().zip(&names).zip(&aliases).map(|((ident, name), aliases)| {
+				quote! { Self::#ident => clap::builder::PossibleValue::new(#name)#(.alias(#aliases))*, }
+			});
+			quote! {
+				impl clap::ValueEnum for #name {
+					fn value_variants<'a>() -> &'a [Self] {
+						Self::ALL
+					}
+
+					fn to_possible_value(&self) -> Option<clap::builder::PossibleValue> {
+						Some(match self {
+							#(#possible_values)*
+						})
+					}
+				}
+			}
+		}
+		false => quote! {},
+	};
+
+	let schema_impl = match container.schema {
+		true => {
+			let all_strings = names.iter().zip(&aliases).flat_map(|(name, aliases)| std::iter::once(name).chain(aliases));
+			quote! {
+				impl v_utils::__internal::schemars::JsonSchema for #name {
+					fn schema_name() -> std::borrow::Cow<'static, str> {
+						#name_str.into()
+					}
+
+					fn json_schema(_: &mut v_utils::__internal::schemars::SchemaGenerator) -> v_utils::__internal::schemars::Schema {
+						v_utils::__internal::schemars::json_schema!({
+							"type": "string",
+							"enum": [#(#all_strings),*],
+						})
+					}
+				}
+			}
+		}
+		false => quote! {},
+	};
+
+	Ok(quote! {
+		impl #name {
+			/// Every variant, in declaration order.
+			pub const ALL: &'static [Self] = &[#(Self::#idents),*];
+
+			/// The name `Display` writes and serde serializes to.
+			pub fn as_str(&self) -> &'static str {
+				match self {
+					#(Self::#idents => #names,)*
+				}
+			}
+		}
+
+		impl std::fmt::Display for #name {
+			fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+				f.pad(self.as_str())
+			}
+		}
+
+		impl std::str::FromStr for #name {
+			type Err = v_utils::__internal::eyre::Report;
+
+			fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+				match s.to_ascii_lowercase().as_str() {
+					#(#parse_arms)*
+					_ => Err(v_utils::__internal::eyre::eyre!("invalid {} `{}`, expected one of: {}", #name_str, s, #expected)),
+				}
+			}
+		}
+
+		impl v_utils::__internal::serde::Serialize for #name {
+			fn serialize<S: v_utils::__internal::serde::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+				serializer.serialize_str(self.as_str())
+			}
+		}
+
+		impl<'de> v_utils::__internal::serde::Deserialize<'de> for #name {
+			fn deserialize<D: v_utils::__internal::serde::Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+				let s = <std::borrow::Cow<'de, str> as v_utils::__internal::serde::Deserialize>::deserialize(deserializer)?;
+				<Self as std::str::FromStr>::from_str(&s).map_err(v_utils::__internal::serde::de::Error::custom)
+			}
+		}
+
+		#value_enum_impl
+		#schema_impl
+	})
+}
 /// Derive macro for application settings that integrates config files, environment variables, and CLI flags.
 ///
 /// # Features
@@ -1643,6 +2838,15 @@ pub fn scream_it(input: TokenStream) -> TokenStream {
 /// 4. If confirmed, add the missing field with its default value to the config file
 /// 5. Retry loading the config
 ///
+/// Step 3 is governed by `v_utils::macros::ExtendPolicy`:
+/// `prompt` (the default), `always-extend`, `never-extend`, or `extend-in-memory-only` (use the
+/// default for this run, leave the file alone). Pick one with an `--extend-policy` flag handed to
+/// `try_build_with_policy`, the `<CARGO_PKG_NAME>_EXTEND_POLICY` env var (shouty, so
+/// `MY_APP_EXTEND_POLICY` for `my-app`), or `#[settings(extend_policy = "never-extend")]` on the
+/// struct, in that order of precedence.
+/// `prompt` never blocks a headless run: when stdin is not a terminal it warns and fails as
+/// `never-extend` would.
+///
 /// **Requirements for auto-extension:**
 /// - The Settings struct must derive `Default` and `serde::Serialize`
 /// - All nested structs must also derive `Default` and `serde::Serialize`
@@ -1701,6 +2905,10 @@ pub fn scream_it(input: TokenStream) -> TokenStream {
 /// - `SettingsFlags` — clap-compatible struct for CLI flag overrides
 /// - `SettingsCommand` — clap subcommands for config management (`write-defaults`, `diff`, `schema`)
 /// - `fn try_build(flags: SettingsFlags) -> Result<Self>`
+/// - `fn try_build_with_policy(flags: SettingsFlags, extend_policy: Option<ExtendPolicy>) -> Result<Self>` — same,
+///   with the value of an `--extend-policy` flag declared on `Cli` (`#[arg(long)] extend_policy: Option<ExtendPolicy>`)
+/// - `fn try_build_from(sources: SettingsSources<SettingsFlags>) -> Result<Self>` — same, but from
+///   in-memory file/env/flags only: touches no process-global state and never prompts (for tests)
 /// - `fn write_defaults() -> Result<PathBuf>`
 /// - `fn write_schema() -> Result<PathBuf>` (requires `#[derive(JsonSchema)]`)
 /// - `fn write_module() -> Result<PathBuf>` (requires `#[derive(JsonSchema)]`)
@@ -1761,6 +2969,7 @@ pub fn derive_setings(input: TokenStream) -> proc_macro::TokenStream {
 	// Parse struct-level #[settings(...)] attributes. Unknown idents are rejected.
 	let mut use_env = false;
 	let mut config_name: Option<String> = None;
+	let mut extend_policy_default = quote! { ::v_utils::macros::ExtendPolicy::Prompt };
 	for attr in &ast.attrs {
 		if !attr.path().is_ident("settings") {
 			continue;
@@ -1776,8 +2985,23 @@ pub fn derive_setings(input: TokenStream) -> proc_macro::TokenStream {
 					let _: Token![=] = input.parse()?;
 					let lit: syn::LitStr = input.parse()?;
 					config_name = Some(lit.value());
+				} else if ident == "extend_policy" {
+					let _: Token![=] = input.parse()?;
+					let lit: syn::LitStr = input.parse()?;
+					let variant = match lit.value().as_str() {
+						"prompt" => quote! { Prompt },
+						"always-extend" => quote! { AlwaysExtend },
+						"never-extend" => quote! { NeverExtend },
+						"extend-in-memory-only" => quote! { ExtendInMemoryOnly },
+						other =>
+							return Err(syn::Error::new(
+								lit.span(),
+								format!("unknown extend_policy `{other}`; valid values are: prompt, always-extend, never-extend, extend-in-memory-only"),
+							)),
+					};
+					extend_policy_default = quote! { ::v_utils::macros::ExtendPolicy::#variant };
 				} else {
-					return Err(unknown_attr_ident(&ident, &["use_env", "config_name"]));
+					return Err(unknown_attr_ident(&ident, &["use_env", "config_name", "extend_policy"]));
 				}
 				if input.is_empty() {
 					return Ok(());
@@ -2030,12 +3254,18 @@ pub fn derive_setings(input: TokenStream) -> proc_macro::TokenStream {
 		impl #name {
 			///NB: must have `Cli` struct in the same scope, with clap derived, and `insert_clap_settings!()` macro having had been expanded inside it.
 			pub fn try_build(flags: SettingsFlags) -> Result<Self, ::v_utils::__internal::SettingsError> {
-				Self::try_build_internal(flags, true)
+				Self::try_build_internal(flags, None)
 			}
 
-			fn try_build_internal(flags: SettingsFlags, allow_extend: bool) -> Result<Self, ::v_utils::__internal::SettingsError> {
+			/// [`try_build`](Self::try_build), with the [`ExtendPolicy`](::v_utils::macros::ExtendPolicy) of an `--extend-policy` flag, which takes precedence over the env var and the struct attribute.
+			pub fn try_build_with_policy(flags: SettingsFlags, extend_policy: Option<::v_utils::macros::ExtendPolicy>) -> Result<Self, ::v_utils::__internal::SettingsError> {
+				Self::try_build_internal(flags, extend_policy)
+			}
+
+			fn try_build_internal(flags: SettingsFlags, extend_policy: Option<::v_utils::macros::ExtendPolicy>) -> Result<Self, ::v_utils::__internal::SettingsError> {
 				let path = flags.config.as_ref().map(|p| p.0.clone());
 				let app_name = env!("CARGO_PKG_NAME");
+				let policy = Self::resolve_extend_policy(extend_policy, std::env::var(Self::extend_policy_env_var()).ok())?;
 				let config_name = #config_name_expr;
 
 				#xdg_conf_dir
@@ -2108,6 +3338,51 @@ pub fn derive_setings(input: TokenStream) -> proc_macro::TokenStream {
 					}
 				};
 
+				Self::finish_build(raw, file_config, config_path, err_msg, flags, policy)
+			}
+
+			/// Build from in-memory sources only: no config-file search, no process env, and never a
+			/// confirmation prompt — with no file on disk there is nothing to extend, so a missing field
+			/// is an error unless the [`ExtendPolicy`](::v_utils::macros::ExtendPolicy) is `ExtendInMemoryOnly`.
+			/// Precedence is the same as `try_build`'s — env < file < flags. `sources.flags.config` is
+			/// ignored; the file, if any, comes from `sources.file`.
+			///
+			/// Meant for tests, which can then run in parallel without temp files or `set_var` races.
+			pub fn try_build_from(sources: ::v_utils::macros::SettingsSources<SettingsFlags>) -> Result<Self, ::v_utils::__internal::SettingsError> {
+				let app_name = env!("CARGO_PKG_NAME");
+				let ::v_utils::macros::SettingsSources { file, env, flags } = sources;
+				let policy = Self::resolve_extend_policy(None, env.get(&Self::extend_policy_env_var()).cloned())?;
+
+				let env_source = ::v_utils::__internal::config::Environment::with_prefix(app_name)
+					.separator("__")
+					.source(Some(env.into_iter().collect()));
+				let mut builder = ::v_utils::__internal::config::Config::builder().add_source(env_source);
+				let file_config = match &file {
+					Some((format, content)) => {
+						builder = builder.add_source(::v_utils::__internal::config::File::from_str(content, *format));
+						::v_utils::__internal::config::Config::builder()
+							.add_source(::v_utils::__internal::config::File::from_str(content, *format))
+							.build()
+							.ok()
+					}
+					None => None,
+				};
+				let raw = builder.add_source(flags.clone()).build()?;
+
+				let err_msg = "Could not construct config from in-memory sources (conf, env, flags).".to_owned();
+				Self::finish_build(raw, file_config, None, err_msg, flags, policy)
+			}
+
+			/// Shared tail of `try_build_internal` and `try_build_from`: validate the aggregated config,
+			/// then deserialize it, resolving a missing field per `policy`.
+			fn finish_build(
+				raw: ::v_utils::__internal::config::Config,
+				file_config: Option<::v_utils::__internal::config::Config>,
+				config_path: Option<std::path::PathBuf>,
+				err_msg: String,
+				flags: SettingsFlags,
+				policy: ::v_utils::macros::ExtendPolicy,
+			) -> Result<Self, ::v_utils::__internal::SettingsError> {
 				// Check for unknown configuration fields
 				if let Some(ref file_cfg) = file_config {
 					Self::warn_unknown_fields(file_cfg);
@@ -2129,40 +3404,98 @@ pub fn derive_setings(input: TokenStream) -> proc_macro::TokenStream {
 					}
 				}
 
-				// Deserialize with serde (which supports MyConfigPrimitives custom deserializer)
-				match raw.try_deserialize() {
-					Ok(config) => Ok(config),
-					Err(e) => {
-						// Check if this is a missing field error and we can extend the config
-						let error_str = e.to_string();
-						if allow_extend {
-							if let Some(missing_field) = Self::parse_missing_field(&error_str) {
-								if let Some(ref config_path) = config_path {
-									// Get default values and offer to extend config
-									// Uses autoref specialization: returns Some if Default+Serialize, None otherwise
-									use __settings_default_provider::GetDefault as _;
-									let wrapper = __settings_default_provider::Wrapper::<Self>(std::marker::PhantomData);
-									if let Some(default_value) = (&wrapper).get_default_for_path(&missing_field) {
-										let prompt = format!(
-											"Missing configuration field \"{}\". Extend config with default value {}?",
-											missing_field,
-											default_value
-										);
-										if flags.yes || matches!(::v_utils::io::confirmation(&prompt).flush_blocking(), ::v_utils::io::ConfirmResult::Yes) {
-											if let Err(extend_err) = Self::extend_config_file(config_path, &missing_field, &default_value) {
-												eprintln!("Warning: Failed to extend config: {}", extend_err);
-											} else {
-												eprintln!("Extended config with default for \"{}\"", missing_field);
-												// Retry building - recursive call with same flags
-												return Self::try_build_internal(flags, true);
-											}
-										}
-									}
+				// Deserialize with serde (which supports MyConfigPrimitives custom deserializer).
+				// A missing field that has a default is resolved per `policy`; `ExtendInMemoryOnly`
+				// patches `raw` and goes around again, so several missing fields resolve in one call.
+				let mut raw = raw;
+				let mut patched_in_memory: Vec<String> = Vec::new();
+				loop {
+					let e = match raw.clone().try_deserialize() {
+						Ok(config) => return Ok(config),
+						Err(e) => e,
+					};
+					// Uses autoref specialization: returns Some if Default+Serialize, None otherwise
+					use __settings_default_provider::GetDefault as _;
+					let wrapper = __settings_default_provider::Wrapper::<Self>(std::marker::PhantomData);
+					let missing = Self::parse_missing_field(&e.to_string())
+						.filter(|field| !patched_in_memory.contains(field))
+						.and_then(|field| (&wrapper).get_default_for_path(&field).map(|default_value| (field, default_value)));
+					let Some((missing_field, default_value)) = missing else {
+						return Err(::v_utils::__internal::eyre::eyre!("{}\n\nRoot cause: {}", err_msg, e).into());
+					};
+
+					// A prompt nobody can answer would block (systemd, CI) or read EOF as "no"; say so instead.
+					let policy = match policy {
+						::v_utils::macros::ExtendPolicy::Prompt if !flags.yes && config_path.is_some() && !std::io::IsTerminal::is_terminal(&std::io::stdin()) => {
+							eprintln!(
+								"warning: not prompting to extend config with missing field \"{missing_field}\": stdin is not a terminal. Set `{}` to choose what happens instead.",
+								Self::extend_policy_env_var()
+							);
+							::v_utils::macros::ExtendPolicy::NeverExtend
+						}
+						policy => policy,
+					};
+					match (policy, &config_path) {
+						(::v_utils::macros::ExtendPolicy::Prompt | ::v_utils::macros::ExtendPolicy::AlwaysExtend, Some(config_path)) => {
+							let accepted = policy == ::v_utils::macros::ExtendPolicy::AlwaysExtend || flags.yes || {
+								let prompt = format!(
+									"Missing configuration field \"{}\". Extend config with default value {}?",
+									missing_field,
+									default_value
+								);
+								matches!(::v_utils::io::confirmation(&prompt).flush_blocking(), ::v_utils::io::ConfirmResult::Yes)
+							};
+							if accepted {
+								if let Err(extend_err) = Self::extend_config_file(config_path, &missing_field, &default_value) {
+									eprintln!("Warning: Failed to extend config: {}", extend_err);
+								} else {
+									eprintln!("Extended config with default for \"{}\"", missing_field);
+									// Retry building - recursive call with same flags
+									return Self::try_build_internal(flags, Some(policy));
 								}
 							}
 						}
-						Err(::v_utils::__internal::eyre::eyre!("{}\n\nRoot cause: {}", err_msg, e).into())
+						(::v_utils::macros::ExtendPolicy::ExtendInMemoryOnly, _) => {
+							eprintln!("Using default value {default_value} for missing configuration field \"{missing_field}\" (config file left untouched)");
+							// "a.b.c" + v  ->  { "a": { "b": { "c": v } } }, layered over everything else.
+							let patch = missing_field.rsplit('.').fold(default_value, |inner, key| {
+								let mut map = ::v_utils::__internal::serde_json::Map::new();
+								map.insert(key.to_owned(), inner);
+								::v_utils::__internal::serde_json::Value::Object(map)
+							});
+							raw = ::v_utils::__internal::config::Config::builder()
+								.add_source(raw)
+								.add_source(::v_utils::__internal::config::File::from_str(&patch.to_string(), ::v_utils::__internal::config::FileFormat::Json))
+								.build()?;
+							patched_in_memory.push(missing_field);
+							continue;
+						}
+						_ => {}
 					}
+					return Err(::v_utils::__internal::eyre::eyre!("{}\n\nRoot cause: {}", err_msg, e).into());
+				}
+			}
+
+			/// Env var consulted for the [`ExtendPolicy`](::v_utils::macros::ExtendPolicy) when no `--extend-policy` flag is given.
+			/// Single `_`, so it stays out of the `<CARGO_PKG_NAME>__<FIELD>` namespace the config itself is read from.
+			fn extend_policy_env_var() -> String {
+				format!("{}_EXTEND_POLICY", env!("CARGO_PKG_NAME").to_uppercase().replace('-', "_"))
+			}
+
+			/// Precedence: `--extend-policy` flag > `<CARGO_PKG_NAME>_EXTEND_POLICY` env > `#[settings(extend_policy = "..")]` > `Prompt`.
+			fn resolve_extend_policy(flag: Option<::v_utils::macros::ExtendPolicy>, env_value: Option<String>) -> Result<::v_utils::macros::ExtendPolicy, ::v_utils::__internal::SettingsError> {
+				if let Some(policy) = flag {
+					return Ok(policy);
+				}
+				match env_value {
+					Some(s) => s.parse().map_err(|_| {
+						::v_utils::__internal::eyre::eyre!(
+							"Invalid `{}={s}`; expected one of: prompt, always-extend, never-extend, extend-in-memory-only",
+							Self::extend_policy_env_var()
+						)
+						.into()
+					}),
+					None => Ok(#extend_policy_default),
 				}
 			}
 
@@ -3682,6 +5015,204 @@ fn _single_option_wrapped_ty(ty: &syn::Type) -> proc_macro2::TokenStream {
 
 //,}}}
 
+// wrap_err {{{
+/// `#[sysexit(..)]`, `#[help("..")]` and `#[code(..)]` of a `#[wrap_err]` variant or struct
+#[derive(Default)]
+struct WrapErrAttrs {
+	sysexit: Option<syn::Ident>,
+	help: Option<syn::LitStr>,
+	code: Option<syn::Path>,
+}
+
+impl WrapErrAttrs {
+	/// Takes them off `attrs`, as they aren't real attributes.
+	fn take(attrs: &mut Vec<syn::Attribute>) -> syn::Result<Self> {
+		let mut result = Self::default();
+		let mut kept = Vec::with_capacity(attrs.len());
+		for attr in attrs.drain(..) {
+			let malformed = |msg: &str| syn::Error::new_spanned(&attr, msg);
+			if attr.path().is_ident("sysexit") {
+				result.sysexit = Some(attr.parse_args().map_err(|_| malformed("#[sysexit(..)] takes a `Sysexit` variant, e.g. `#[sysexit(Config)]`"))?);
+			} else if attr.path().is_ident("help") {
+				result.help = Some(attr.parse_args().map_err(|_| malformed("#[help(..)] takes a string literal, e.g. `#[help(\"check the config\")]`"))?);
+			} else if attr.path().is_ident("code") {
+				result.code = Some(attr.parse_args().map_err(|_| malformed("#[code(..)] takes a path, e.g. `#[code(my_app::bad_value)]`"))?);
+			} else {
+				kept.push(attr);
+			}
+		}
+		*attrs = kept;
+		Ok(result)
+	}
+}
+
+/// One `match self` arm of the generated impls: a variant, or the struct itself.
+struct DiagnosticArm {
+	/// `Self::Variant`, or `Self`
+	path: proc_macro2::TokenStream,
+	/// Named fields, available to `#[help]` interpolation
+	fields: Vec<syn::Ident>,
+	own: bool,
+	attrs: WrapErrAttrs,
+}
+
+impl DiagnosticArm {
+	/// Binds the given named fields, or `source` for `#[own]`.
+	fn pattern(&self, bound: &[syn::Ident]) -> proc_macro2::TokenStream {
+		let path = &self.path;
+		match self.own {
+			true => quote! { #path(source) },
+			false => quote! { #path { #(#bound,)* .. } },
+		}
+	}
+}
+
+fn derives_diagnostic(attrs: &[syn::Attribute]) -> bool {
+	attrs.iter().filter(|a| a.path().is_ident("derive")).any(|a| {
+		a.parse_args_with(syn::punctuated::Punctuated::<syn::Path, Token![,]>::parse_terminated)
+			.is_ok_and(|paths| paths.iter().any(|p| p.segments.last().is_some_and(|s| s.ident == "Diagnostic")))
+	})
+}
+
+/// `SysexitCode`, if anything has a `#[sysexit]`. `#[own]` arms without one take their source's.
+fn sysexit_impl(name: &syn::Ident, generics: &syn::Generics, arms: &[DiagnosticArm]) -> proc_macro2::TokenStream {
+	if arms.iter().all(|arm| arm.attrs.sysexit.is_none()) {
+		return quote! {};
+	}
+	let arms = arms.iter().filter_map(|arm| {
+		let pattern = arm.pattern(&[]);
+		match &arm.attrs.sysexit {
+			Some(sysexit) => Some(quote_spanned! { sysexit.span()=> #[allow(unused_variables)] #pattern => ::v_utils::utils::Sysexit::#sysexit, }),
+			None if arm.own => Some(quote! { #pattern => ::v_utils::utils::SysexitCode::sysexit(source), }),
+			None => None,
+		}
+	});
+	let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+	quote! {
+		impl #impl_generics ::v_utils::utils::SysexitCode for #name #ty_generics #where_clause {
+			fn sysexit(&self) -> ::v_utils::utils::Sysexit {
+				#[allow(unreachable_patterns)]
+				match self {
+					#(#arms)*
+					_ => ::v_utils::utils::Sysexit::None,
+				}
+			}
+		}
+	}
+}
+
+/// `miette::Diagnostic`, for `#[wrap_err(diagnostic)]`
+fn diagnostic_impl(name: &syn::Ident, generics: &syn::Generics, arms: &[DiagnosticArm], diagnostic: bool) -> proc_macro2::TokenStream {
+	if !diagnostic {
+		return quote! {};
+	}
+	let miette = quote! { ::v_utils::__internal::miette };
+
+	let help_arms = arms.iter().filter_map(|arm| match &arm.attrs.help {
+		Some(help) => {
+			// Only what the text refers to, so the rest don't trip `unused_variables`.
+			let text = help.value();
+			let bound: Vec<syn::Ident> = arm
+				.fields
+				.iter()
+				.filter(|f| text.contains(&format!("{{{f}}}")) || text.contains(&format!("{{{f}:")))
+				.cloned()
+				.collect();
+			let pattern = arm.pattern(&bound);
+			let help = match text.contains('{') {
+				true => quote! { ::std::format!(#help) },
+				false => quote! { #help },
+			};
+			Some(quote! { #[allow(unused_variables)] #pattern => ::std::option::Option::Some(::std::boxed::Box::new(#help)), })
+		}
+		None if arm.own => {
+			let pattern = arm.pattern(&[]);
+			Some(quote! { #pattern => #miette::Diagnostic::help(source), })
+		}
+		None => None,
+	});
+	let code_arms = arms.iter().filter_map(|arm| match &arm.attrs.code {
+		Some(code) => {
+			let pattern = arm.pattern(&[]);
+			let code = code.segments.iter().map(|s| s.ident.to_string()).collect::<Vec<_>>().join("::");
+			Some(quote! { #[allow(unused_variables)] #pattern => ::std::option::Option::Some(::std::boxed::Box::new(#code)), })
+		}
+		None if arm.own => {
+			let pattern = arm.pattern(&[]);
+			Some(quote! { #pattern => #miette::Diagnostic::code(source), })
+		}
+		None => None,
+	});
+	// The rest only ever comes from `#[own]` sources.
+	let delegate = |method: proc_macro2::TokenStream| {
+		let arms = arms.iter().filter(|arm| arm.own).map(|arm| {
+			let pattern = arm.pattern(&[]);
+			quote! { #pattern => #miette::Diagnostic::#method(source), }
+		});
+		quote! {
+			#[allow(unreachable_patterns)]
+			match self {
+				#(#arms)*
+				_ => ::std::option::Option::None,
+			}
+		}
+	};
+	let (severity, url, source_code, labels, related, diagnostic_source) = (
+		delegate(quote! { severity }),
+		delegate(quote! { url }),
+		delegate(quote! { source_code }),
+		delegate(quote! { labels }),
+		delegate(quote! { related }),
+		delegate(quote! { diagnostic_source }),
+	);
+
+	let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+	quote! {
+		impl #impl_generics #miette::Diagnostic for #name #ty_generics #where_clause {
+			fn code<'a>(&'a self) -> ::std::option::Option<::std::boxed::Box<dyn ::std::fmt::Display + 'a>> {
+				#[allow(unreachable_patterns)]
+				match self {
+					#(#code_arms)*
+					_ => ::std::option::Option::None,
+				}
+			}
+
+			fn help<'a>(&'a self) -> ::std::option::Option<::std::boxed::Box<dyn ::std::fmt::Display + 'a>> {
+				#[allow(unreachable_patterns)]
+				match self {
+					#(#help_arms)*
+					_ => ::std::option::Option::None,
+				}
+			}
+
+			fn severity(&self) -> ::std::option::Option<#miette::Severity> {
+				#severity
+			}
+
+			fn url<'a>(&'a self) -> ::std::option::Option<::std::boxed::Box<dyn ::std::fmt::Display + 'a>> {
+				#url
+			}
+
+			fn source_code(&self) -> ::std::option::Option<&dyn #miette::SourceCode> {
+				#source_code
+			}
+
+			fn labels(&self) -> ::std::option::Option<::std::boxed::Box<dyn ::std::iter::Iterator<Item = #miette::LabeledSpan> + '_>> {
+				#labels
+			}
+
+			fn related<'a>(&'a self) -> ::std::option::Option<::std::boxed::Box<dyn ::std::iter::Iterator<Item = &'a dyn #miette::Diagnostic> + 'a>> {
+				#related
+			}
+
+			fn diagnostic_source(&self) -> ::std::option::Option<&dyn #miette::Diagnostic> {
+				#diagnostic_source
+			}
+		}
+	}
+}
+//,}}}
+
 /// Reject an unrecognized identifier inside a `#[settings(...)]` / `#[primitives(...)]`
 /// attribute. Shared so every parse site fails loudly (with the same shape of message,
 /// spanned at the offending token) instead of silently ignoring typos. The returned
@@ -3759,12 +5290,18 @@ impl SettingsFieldAttrs {
 }
 
 // make_df! {{{
+/// Where in a row a `make_df!` column's value is: an index into an array, or a dotted path into an object.
+enum DfAt {
+	Index(LitInt),
+	Path(syn::LitStr),
+}
+
 /// Structure to hold the entire macro input
 struct Field {
 	_parens: token::Paren,
-	index: LitInt,
+	at: DfAt,
 	_comma1: Token![,],
-	dtype: Ident,
+	dtype: syn::Type,
 	_comma2: Token![,],
 	name: Ident,
 }
@@ -3773,7 +5310,10 @@ impl Parse for Field {
 		let content;
 		Ok(Field {
 			_parens: syn::parenthesized!(content in input),
-			index: content.parse()?,
+			at: match content.peek(syn::LitStr) {
+				true => DfAt::Path(content.parse()?),
+				false => DfAt::Index(content.parse()?),
+			},
 			_comma1: content.parse()?,
 			dtype: content.parse()?,
 			_comma2: content.parse()?,
@@ -3784,25 +5324,164 @@ impl Parse for Field {
 
 /// Structure to hold the entire macro input
 struct DataFrameDef {
-	values_vec: Ident,
+	values_vec: syn::Expr,
 	_arrow: Token![=>],
 	fields: Vec<Field>,
 }
 impl Parse for DataFrameDef {
 	fn parse(input: ParseStream) -> Result<Self, syn::Error> {
-		let values_vec: Ident = input.parse()?;
+		let values_vec = input.parse()?;
 		let _arrow: Token![=>] = input.parse()?;
 
 		let mut fields = Vec::new();
-		//TODO!: add optional comma at the end
 		while !input.is_empty() {
 			fields.push(input.parse()?);
+			let _ = input.parse::<Option<Token![,]>>();
 		}
 
 		Ok(DataFrameDef { values_vec, _arrow, fields })
 	}
 }
 
+/// Column types of `make_df!` and `IntoDataFrame`, other than nullability.
+enum DfScalar {
+	/// `i64`, `i32`, `u64`, `u32`, `f64`, `f32` or `bool`: whatever parses from the JSON value's text
+	Parsed(Ident),
+	String,
+	/// ms timestamps, kept as `i64` until the column is cast
+	Datetime,
+}
+
+impl std::fmt::Display for DfScalar {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::Parsed(ident) => write!(f, "{ident}"),
+			Self::String => write!(f, "String"),
+			Self::Datetime => write!(f, "ms timestamp"),
+		}
+	}
+}
+
+impl DfScalar {
+	const PARSED: [&str; 7] = ["i64", "i32", "u64", "u32", "f64", "f32", "bool"];
+
+	/// `ChunkedArray` accessor on `Column` of the physical type.
+	fn accessor(&self) -> Ident {
+		match self {
+			Self::Parsed(ident) => ident.clone(),
+			Self::String => format_ident!("str"),
+			Self::Datetime => format_ident!("i64"),
+		}
+	}
+}
+
+struct DfColumnType {
+	scalar: DfScalar,
+	nullable: bool,
+}
+
+impl DfColumnType {
+	/// `dtype` of a `make_df!` column: one of `DfScalar`'s, `Datetime`, or an `Option` of either.
+	fn of_make_df(dtype: &syn::Type) -> syn::Result<Self> {
+		let (inner, nullable) = match generic_inner(dtype, "Option") {
+			Some(inner) => (inner, true),
+			None => (dtype, false),
+		};
+		let scalar = match Self::ident(inner) {
+			Some(ident) if ident == "Datetime" => DfScalar::Datetime,
+			Some(ident) => Self::scalar(ident).ok_or_else(|| Self::unsupported(dtype, ", Datetime"))?,
+			None => return Err(Self::unsupported(dtype, ", Datetime")),
+		};
+		Ok(Self { scalar, nullable })
+	}
+
+	/// Type of an `IntoDataFrame` field, with `#[df(datetime)]` making an `i64` a `Datetime`.
+	fn of_field(field: &syn::Field) -> syn::Result<Self> {
+		let mut datetime = false;
+		for attr in field.attrs.iter().filter(|a| a.path().is_ident("df")) {
+			attr.parse_nested_meta(|meta| match meta.path.get_ident() {
+				Some(ident) if ident == "datetime" => {
+					datetime = true;
+					Ok(())
+				}
+				Some(ident) => Err(unknown_attr_ident(ident, &["datetime"])),
+				None => Err(meta.error("expected `datetime`")),
+			})?;
+		}
+
+		let (inner, nullable) = match generic_inner(&field.ty, "Option") {
+			Some(inner) => (inner, true),
+			None => (&field.ty, false),
+		};
+		let scalar = Self::ident(inner).and_then(Self::scalar).ok_or_else(|| Self::unsupported(&field.ty, ""))?;
+		let scalar = match (datetime, scalar) {
+			(true, DfScalar::Parsed(ident)) if ident == "i64" => DfScalar::Datetime,
+			(true, _) => return Err(syn::Error::new_spanned(&field.ty, "#[df(datetime)] is for `i64` ms timestamps")),
+			(false, scalar) => scalar,
+		};
+		Ok(Self { scalar, nullable })
+	}
+
+	fn ident(ty: &syn::Type) -> Option<&Ident> {
+		match ty {
+			syn::Type::Path(type_path) if type_path.qself.is_none() => type_path.path.get_ident(),
+			_ => None,
+		}
+	}
+
+	fn scalar(ident: &Ident) -> Option<DfScalar> {
+		match ident.to_string().as_str() {
+			"String" => Some(DfScalar::String),
+			s if DfScalar::PARSED.contains(&s) => Some(DfScalar::Parsed(ident.clone())),
+			_ => None,
+		}
+	}
+
+	/// `extra`: scalars other than `DfScalar::PARSED` and `String`, each with a leading ", "
+	fn unsupported(ty: &syn::Type, extra: &str) -> syn::Error {
+		let scalars = DfScalar::PARSED.join(", ");
+		syn::Error::new_spanned(ty, format!("unsupported column type; expected one of: {scalars}, String{extra}, or an `Option` of one"))
+	}
+
+	/// Element type of the `Vec` the column is built from.
+	fn rust_ty(&self) -> proc_macro2::TokenStream {
+		let scalar = match &self.scalar {
+			DfScalar::Parsed(ident) => quote! { #ident },
+			DfScalar::String => quote! { String },
+			DfScalar::Datetime => quote! { i64 },
+		};
+		match self.nullable {
+			true => quote! { Option<#scalar> },
+			false => scalar,
+		}
+	}
+
+	/// Suffix turning the `Column` built from the `Vec` into one of the right dtype.
+	fn cast(&self) -> proc_macro2::TokenStream {
+		match self.scalar {
+			DfScalar::Datetime => quote! {
+				.cast(&polars::prelude::DataType::Datetime(polars::prelude::TimeUnit::Milliseconds, None))?
+			},
+			_ => quote! {},
+		}
+	}
+
+	/// `Option` of the scalar, from a reference to a non-null `serde_json::Value`.
+	fn parse_json(&self, v: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+		let ty = match &self.scalar {
+			DfScalar::String => return quote! { #v.as_str().map(str::to_owned) },
+			DfScalar::Parsed(ident) => quote! { #ident },
+			DfScalar::Datetime => quote! { i64 },
+		};
+		quote! {
+			match #v.as_str() {
+				Some(s) => s.parse::<#ty>().ok(),
+				None => #v.to_string().parse::<#ty>().ok(),
+			}
+		}
+	}
+}
+
 //,}}}
 
 //BUG: doesn't convert to SCREAMING_SNAKE_CASE, but simply uppercases everything
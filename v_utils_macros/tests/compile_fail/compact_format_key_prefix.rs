@@ -0,0 +1,9 @@
+use v_utils_macros::CompactFormatNamed;
+
+// `t` would also match the start of every `tp..` param, so which field a param is for would depend on its value.
+#[derive(CompactFormatNamed, Debug)] //~ ERROR: proc-macro derive panicked
+struct Ambiguous {
+	threshold: f64,
+	#[compact(key = "tp")]
+	take_profit: f64,
+}
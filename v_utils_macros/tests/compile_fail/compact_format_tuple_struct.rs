@@ -0,0 +1,7 @@
+use v_utils_macros::CompactFormatNamed;
+
+// Fields are keyed by their names, so there's nothing to key a tuple struct's by.
+#[derive(CompactFormatNamed, Debug)]
+struct Tuple(f64, f64); //~ ERROR: CompactFormatNamed is for structs with named fields
+
+fn main() {}
@@ -0,0 +1,10 @@
+use v_utils_macros::CompactFormatNamed;
+
+// Both go by `ts`, so which one `ts:p0.5` parses as would come down to declaration order.
+#[derive(CompactFormatNamed, Debug)]
+enum Order {
+	TrailingStop { p: f64 },
+	TakeStop { p: f64 }, //~ ERROR: `ts` is a name of both `TrailingStop` and `TakeStop`
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use v_utils_macros::IntoDataFrame;
+
+// Only scalars polars has a column type for, and `Option`s of them.
+#[derive(IntoDataFrame)]
+struct Kline {
+	close: f64,
+	levels: Vec<f64>, //~ ERROR: unsupported column type
+}
@@ -0,0 +1,11 @@
+use v_utils_macros::Settings;
+
+// An unrecognized `extend_policy` must be a hard error rather than silently falling back
+// to `prompt` — which is exactly the behaviour the attribute is usually there to avoid.
+#[derive(Clone, Debug, Default, Settings)]
+#[settings(extend_policy = "never")] //~ ERROR: unknown extend_policy `never`
+pub struct BadConfig {
+	pub port: u16,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use v_utils_macros::StrEnum;
+
+// Parsing ignores case, so `SELL` couldn't be told apart from `Sell`'s name.
+#[derive(Debug, StrEnum)]
+enum Side {
+	Sell,
+	#[str_enum(alias = "SELL")] //~ ERROR: `SELL` would parse as both `Sell` and `Buy`
+	Buy,
+}
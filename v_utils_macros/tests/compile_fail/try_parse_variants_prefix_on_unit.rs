@@ -0,0 +1,9 @@
+use v_utils_macros::TryParseVariants;
+
+// Unit variants are matched by their name, so a prefix would only say the same thing less precisely.
+#[derive(Debug, TryParseVariants)]
+pub enum Exit {
+	Fixed(f64),
+	#[try_parse(prefix = "n")] //~ ERROR: unit variants are matched by name, not by prefix
+	Never,
+}
@@ -0,0 +1,9 @@
+use v_utils_macros::wrap_err;
+
+// #[foreign] with named fields needs a `source` to wrap; the rest is context.
+#[wrap_err] //~ ERROR: custom attribute panicked
+#[derive(Debug)]
+pub enum ReadError {
+	#[foreign]
+	Io { path: std::path::PathBuf },
+}
@@ -0,0 +1,12 @@
+use v_utils_macros::wrap_err;
+
+// `#[sysexit]` names a `Sysexit` variant, rather than spelling out the code.
+#[wrap_err]
+#[derive(Debug, thiserror::Error)]
+#[sysexit(78)] //~ ERROR: #[sysexit(..)] takes a `Sysexit` variant
+#[error("bad config")]
+pub struct ConfigError {
+	field: String,
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use v_utils_macros::wrap_err;
+
+// `diagnostic` is the only option; the `miette::Diagnostic` impl is opted into per type.
+#[wrap_err(miette)] //~ ERROR: unsupported #[wrap_err] option
+#[derive(Debug)]
+pub struct MyError {
+	field: String,
+}
+
+fn main() {}
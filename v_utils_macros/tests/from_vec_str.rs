@@ -1,3 +1,4 @@
+use v_utils::utils::compact::{FromVecStrError, FromVecStrIssue};
 use v_utils_macros::{CompactFormatNamed, OptionalFieldsFromVecStr, VecFieldsFromVecStr};
 
 #[test]
@@ -22,6 +23,16 @@ fn test() {
 		}
 	);
 
+	let o3 = OptionalProtocols::try_from(vec!["0.3", "take_profit_stop_loss=0.1"]).unwrap();
+	assert_eq!(
+		o3,
+		OptionalProtocols {
+			trailing_stop: None,
+			take_profit_stop_loss: Some(0.1),
+			leading_crosses: Some(0.3)
+		}
+	);
+
 	let v1 = VecProtocols::try_from(vec!["ts:p0.2", "ts:p-0.2"]).unwrap();
 	assert_eq!(
 		v1,
@@ -50,3 +61,62 @@ struct VecProtocols {
 	take_profit_stop_loss: Vec<f64>,
 	leading_crosses: Vec<f64>,
 }
+
+#[derive(Clone, Debug, OptionalFieldsFromVecStr, PartialEq)]
+#[from_vec_str(strict)]
+struct StrictProtocols {
+	trailing_stop: Option<TrailingStop>,
+	leading_crosses: Option<f64>,
+	max_positions: Option<u32>,
+}
+
+#[test]
+fn errors() {
+	let err = OptionalProtocols::try_from(vec!["0.1", "0.2", "0.3", "trailing_stop=0.5"]).unwrap_err();
+	assert_eq!(err.issues[1], FromVecStrIssue::Unmatched("0.3".to_owned()));
+	insta::assert_snapshot!(err, @r#"
+ couldn't make OptionalProtocols out of the tokens
+   `trailing_stop=0.5`: not a trailing_stop: expected one of ["ts", "TS", "trailingstop", "TRAILINGSTOP", "TrailingStop", "trailing_stop"] at byte 0 of `0.5`, found `0.5`
+   `0.3`: fits none of the fields left
+ "#);
+
+	// `3` is both an f64 and a u32
+	let err = StrictProtocols::try_from(vec!["3", "ts:p0.1", "trailing_stop=ts:p0.2"]).unwrap_err();
+	insta::assert_snapshot!(err, @r#"
+ couldn't make StrictProtocols out of the tokens
+   `3`: could be any of leading_crosses, max_positions; say which with `key=value`
+   trailing_stop given more than once: `trailing_stop=ts:p0.2`, `ts:p0.1`
+ "#);
+	let err = StrictProtocols::try_from(vec!["leading_crosses=0.1", "leading_crosses=0.2"]).unwrap_err();
+	insta::assert_snapshot!(err, @r#"
+ couldn't make StrictProtocols out of the tokens
+   leading_crosses given more than once: `leading_crosses=0.1`, `leading_crosses=0.2`
+ "#);
+
+	// `ts:p0.2` only fits `trailing_stop`, which `ts:p0.1` already took
+	let err = StrictProtocols::try_from(vec!["ts:p0.1", "ts:p0.2"]).unwrap_err();
+	assert_eq!(
+		err.issues,
+		vec![FromVecStrIssue::Duplicate {
+			field: "trailing_stop",
+			tokens: vec!["ts:p0.1".to_owned(), "ts:p0.2".to_owned()],
+		}]
+	);
+
+	let ok = StrictProtocols::try_from(vec!["max_positions=3", "3", "ts:p0.1"]).unwrap();
+	assert_eq!(
+		ok,
+		StrictProtocols {
+			trailing_stop: Some(TrailingStop { percent: 0.1 }),
+			leading_crosses: Some(3.),
+			max_positions: Some(3),
+		}
+	);
+	assert_eq!(
+		OptionalProtocols::try_from(vec!["x"]).unwrap_err(),
+		FromVecStrError {
+			ty: "OptionalProtocols",
+			issues: vec![FromVecStrIssue::Unmatched("x".to_owned())],
+		}
+	);
+}
@@ -2,6 +2,7 @@
 
 use std::str::FromStr;
 
+use v_utils::utils::compact::TryParseError;
 use v_utils_macros::{CompactFormatMap, CompactFormatNamed, TryParseVariants};
 
 #[derive(CompactFormatNamed, Debug, PartialEq)]
@@ -72,6 +73,199 @@ pub struct PositionParams {
 	pub count: u32,
 }
 
+#[derive(Clone, CompactFormatNamed, Debug, PartialEq)]
+#[compact(serde, schema)]
+pub enum Strategy {
+	Order {
+		position: Position,
+		count: u32,
+	},
+	TrailingStop {
+		percent: f64,
+		#[compact(default = 1)]
+		step: u32,
+	},
+	Idle,
+}
+
+#[derive(CompactFormatNamed, Debug, PartialEq)]
+pub struct Bracket {
+	#[compact(key = "tp")]
+	pub take_profit: f64,
+	#[compact(key = "ts")]
+	pub trailing_stop: Option<f64>,
+	pub note: String,
+	pub levels: Vec<f64>,
+	#[compact(key = "tags", default)]
+	pub tags: Vec<String>,
+	pub exit: Option<TrailingStop>,
+}
+
+#[derive(Debug, PartialEq, schemars::JsonSchema, serde::Deserialize, serde::Serialize)]
+struct StrategyConfig {
+	strategy: Strategy,
+	fallback: Strategy,
+}
+
+#[test]
+fn enum_variants_as_prefixes() {
+	let order = Strategy::Order {
+		position: Position { take_profit: 0.48, stop_loss: 0.52 },
+		count: 50,
+	};
+	insta::assert_snapshot!(order.to_string(), @"order:p{t=0.48;s=0.52}:c50");
+	assert_eq!(Strategy::from_str("order:p{t=0.48;s=0.52}:c50").unwrap(), order);
+
+	assert_eq!(Strategy::from_str("ts:p0.5").unwrap(), Strategy::TrailingStop { percent: 0.5, step: 1 });
+	assert_eq!(Strategy::TrailingStop { percent: 0.5, step: 2 }.to_string(), "ts:p0.5:s2");
+	assert_eq!(Strategy::from_str("idle").unwrap(), Strategy::Idle);
+	assert_eq!(Strategy::Idle.to_string(), "idle");
+
+	assert!(Strategy::from_str("idle:x1").is_err());
+	let err = Strategy::from_str("cancel:i7").unwrap_err().to_string();
+	assert!(err.contains("\"order\"") && err.contains("\"ts\""), "{err}");
+}
+
+#[test]
+fn explicit_keys_quoting_and_collections() {
+	let bracket = Bracket {
+		take_profit: 0.5,
+		trailing_stop: None,
+		note: "a:b {x".to_owned(),
+		levels: vec![1.0, 2.5],
+		tags: vec!["x,y".to_owned(), String::new()],
+		exit: Some(TrailingStop { percent: 0.5, some_other_field: 42 }),
+	};
+	insta::assert_snapshot!(bracket.to_string(), @r#"bracket:tp0.5:n"a:b {x":l[1,2.5]:tags["x,y",""]:e"ts:p0.5:s42""#);
+	assert_eq!(Bracket::from_str(&bracket.to_string()).unwrap(), bracket);
+
+	assert_eq!(
+		Bracket::from_str("bracket:l[]:tp1:ts0.2:n").unwrap(),
+		Bracket {
+			take_profit: 1.0,
+			trailing_stop: Some(0.2),
+			note: String::new(),
+			levels: vec![],
+			tags: vec![],
+			exit: None,
+		}
+	);
+	assert_eq!(Bracket::from_str("bracket:tp1:tp2:n:l[]").unwrap_err().found(), "tp");
+	assert_eq!(Bracket::from_str("bracket:tp1:n:l[]:x1").unwrap_err().found(), "x1");
+	assert!(Bracket::from_str("bracket:tp1:n\"open:l[]").is_err());
+}
+
+#[test]
+fn parse_errors_point_at_the_offending_part() {
+	let err = Strategy::from_str("order:p{t=0.48;s=x}:c50").unwrap_err();
+	assert_eq!((err.offset, err.found(), err.expected.as_str()), (17, "x", "a `f64`"));
+	assert_eq!(err.reason.as_deref(), Some("invalid float literal"));
+
+	let err = Strategy::from_str("ts:p0.5:q1").unwrap_err();
+	insta::assert_snapshot!(err, @r#"expected a field keyed by any of ["p", "s"] at byte 8 of `ts:p0.5:q1`, found `q1`"#);
+
+	let err = Position::from_str("{t=0.5}").unwrap_err();
+	assert_eq!((err.offset, err.len, err.expected.as_str()), (6, 0, "the field 'stop_loss' (key `s`)"));
+
+	let mut rendered = String::new();
+	miette::NarratableReportHandler::new()
+		.render_report(&mut rendered, &Bracket::from_str(r#"bracket:tp1:n"a\qb":l[]"#).unwrap_err())
+		.unwrap();
+	insta::assert_snapshot!(rendered.trim_end(), @r#"
+	expected `\"` or `\\` for an escape at byte 15 of `bracket:tp1:n"a\qb":l[]`, found `\q`
+	    Diagnostic severity: error
+	Begin snippet starting at line 1, column 1
+
+	snippet line 1: bracket:tp1:n"a\qb":l[]
+	    label at line 1, columns 16 to 17: expected `\"` or `\\` for an escape
+	"#);
+}
+
+#[test]
+fn grammar_matches_what_parses() {
+	let strategy = regex::Regex::new(&format!("^(?:{})$", Strategy::compact_grammar())).unwrap();
+	for s in ["order:p{t=0.48;s=0.52}:c50", "ts:p0.5", "ts:s2:p-1e3", "idle", "idle:_"] {
+		assert!(Strategy::from_str(s).is_ok() && strategy.is_match(s), "{s}");
+	}
+	for s in ["cancel:i7", "ts:p0.5x", "order:p{t=0.48:c50", "ts:q1"] {
+		assert!(Strategy::from_str(s).is_err() && !strategy.is_match(s), "{s}");
+	}
+
+	let bracket = regex::Regex::new(&format!("^(?:{})$", Bracket::compact_grammar())).unwrap();
+	assert!(bracket.is_match(r#"bracket:tp0.5:n"a:b {x":l[1,2.5]:tags["x,y",""]:e"ts:p0.5:s42""#));
+	assert!(!bracket.is_match("bracket:tp0.5:l[1,x]"));
+
+	let schema = serde_json::to_value(schemars::schema_for!(StrategyConfig)).unwrap();
+	let strategy_schema = &schema["$defs"]["Strategy"];
+	assert_eq!(strategy_schema["type"], "string");
+	assert_eq!(strategy_schema["pattern"], format!("^(?:{})$", Strategy::compact_grammar()));
+}
+
+#[derive(Debug, PartialEq, TryParseVariants)]
+enum Exit {
+	#[try_parse(prefix = "ts")]
+	Trailing(TrailingStop),
+	Fixed(f64),
+	Never,
+}
+
+#[derive(Debug, PartialEq, TryParseVariants)]
+#[try_parse(strict)]
+enum Amount {
+	Whole(u32),
+	Fractional(f64),
+}
+
+#[test]
+fn try_parse_variants_reasons_prefixes_and_strict() {
+	assert_eq!(Exit::from_str("ts:p0.5:s42").unwrap(), Exit::Trailing(TrailingStop { percent: 0.5, some_other_field: 42 }));
+	assert_eq!(Exit::from_str("1.5").unwrap(), Exit::Fixed(1.5));
+	assert_eq!(Exit::from_str("never").unwrap(), Exit::Never);
+
+	// Past the prefix, the other variants aren't tried.
+	let Err(TryParseError::NoMatch { attempts, .. }) = Exit::from_str("ts:p0.5:q1") else {
+		panic!()
+	};
+	assert_eq!(attempts.iter().map(|(variant, _)| *variant).collect::<Vec<_>>(), ["Trailing"]);
+
+	insta::assert_snapshot!(Exit::from_str("soon").unwrap_err(), @r#"
+	`soon` is none of Exit's variants
+	  Trailing: doesn't start with `ts`
+	  Fixed: invalid float literal
+	  Never: not one of ["never", "NEVER", "Never"]
+	"#);
+
+	assert_eq!(Amount::from_str("1.5").unwrap(), Amount::Fractional(1.5));
+	assert_eq!(
+		Amount::from_str("3").unwrap_err(),
+		TryParseError::Ambiguous {
+			ty: "Amount",
+			input: "3".to_owned(),
+			variants: vec!["Whole", "Fractional"],
+		}
+	);
+}
+
+#[test]
+fn serde_through_the_compact_string() {
+	let config = StrategyConfig {
+		strategy: Strategy::Order {
+			position: Position { take_profit: 0.48, stop_loss: 0.52 },
+			count: 50,
+		},
+		fallback: Strategy::Idle,
+	};
+	let json = serde_json::to_string(&config).unwrap();
+	assert_eq!(json, r#"{"strategy":"order:p{t=0.48;s=0.52}:c50","fallback":"idle"}"#);
+	assert_eq!(serde_json::from_str::<StrategyConfig>(&json).unwrap(), config);
+
+	let toml: StrategyConfig = toml::from_str("strategy = \"ts:p0.5\"\nfallback = \"idle\"").unwrap();
+	assert_eq!(toml.strategy, Strategy::TrailingStop { percent: 0.5, step: 1 });
+
+	let err = serde_json::from_str::<StrategyConfig>(r#"{"strategy":"order:c5","fallback":"idle"}"#).unwrap_err();
+	assert!(err.to_string().contains("expected the required field 'position' (key `p`)"), "{err}");
+}
+
 #[test]
 fn test() {
 	{
@@ -1,5 +1,5 @@
 use serde_json::Value;
-use v_utils_macros::make_df;
+use v_utils_macros::{IntoDataFrame, make_df};
 
 #[test]
 fn test() {
@@ -12,7 +12,8 @@ fn test() {
 		(0, i64, open_time)
 		(4, f64, close)
 		(5, f64, volume)
-	];
+	]
+	.unwrap();
 	insta::assert_debug_snapshot!(df, @r#"
  shape: (3, 3)
  ┌───────────────┬──────────┬───────────┐
@@ -26,3 +27,84 @@ fn test() {
  └───────────────┴──────────┴───────────┘
  "#);
 }
+
+#[test]
+fn object_rows() {
+	// binance aggTrades, with a nested field and a missing one added
+	let json = r#"[
+		{"a": 26129, "p": "0.01633102", "q": "4.70443515", "T": 1498793709153, "m": true, "meta": {"venue": "binance"}},
+		{"a": 26130, "p": "0.01633200", "q": "1.00000000", "T": 1498793709160, "m": null, "meta": {"venue": "binance"}},
+		{"a": 26131, "p": 0.016331, "q": "2.5", "T": "1498793709999", "meta": {"venue": "bybit"}}
+	]"#;
+	let rows: Vec<Value> = serde_json::from_str(json).unwrap();
+
+	let df = make_df![
+		&rows =>
+		("T", Datetime, time)
+		("p", f64, price)
+		("q", f32, qty)
+		("m", Option<bool>, is_maker)
+		("meta.venue", String, venue),
+	]
+	.unwrap();
+	insta::assert_debug_snapshot!(df, @r#"
+ shape: (3, 5)
+ ┌─────────────────────────┬──────────┬──────────┬──────────┬─────────┐
+ │ time                    ┆ price    ┆ qty      ┆ is_maker ┆ venue   │
+ │ ---                     ┆ ---      ┆ ---      ┆ ---      ┆ ---     │
+ │ datetime[ms]            ┆ f64      ┆ f32      ┆ bool     ┆ str     │
+ ╞═════════════════════════╪══════════╪══════════╪══════════╪═════════╡
+ │ 2017-06-30 03:35:09.153 ┆ 0.016331 ┆ 4.704435 ┆ true     ┆ binance │
+ │ 2017-06-30 03:35:09.160 ┆ 0.016332 ┆ 1.0      ┆ null     ┆ binance │
+ │ 2017-06-30 03:35:09.999 ┆ 0.016331 ┆ 2.5      ┆ null     ┆ bybit   │
+ └─────────────────────────┴──────────┴──────────┴──────────┴─────────┘
+ "#);
+
+	let err = make_df![&rows => ("meta.venue", i64, venue)].unwrap_err();
+	insta::assert_snapshot!(err, @r#"row 0: `venue` is "binance", expected i64"#);
+	let err = make_df![&rows => ("m", bool, is_maker)].unwrap_err();
+	insta::assert_snapshot!(err, @r#"row 1: nothing at "m" for `is_maker`"#);
+}
+
+#[derive(Clone, Debug, PartialEq, IntoDataFrame)]
+struct Kline {
+	#[df(datetime)]
+	open_time: i64,
+	close: f64,
+	trades: Option<u32>,
+	symbol: String,
+}
+
+#[test]
+fn derive_round_trip() {
+	let klines = vec![
+		Kline {
+			open_time: 1732651500000,
+			close: 91921.37,
+			trades: Some(65086),
+			symbol: "BTCUSDT".to_owned(),
+		},
+		Kline {
+			open_time: 1732651800000,
+			close: 91595.99,
+			trades: None,
+			symbol: "BTCUSDT".to_owned(),
+		},
+	];
+	let df = Kline::to_df(&klines).unwrap();
+	insta::assert_debug_snapshot!(df, @r#"
+ shape: (2, 4)
+ ┌─────────────────────┬──────────┬────────┬─────────┐
+ │ open_time           ┆ close    ┆ trades ┆ symbol  │
+ │ ---                 ┆ ---      ┆ ---    ┆ ---     │
+ │ datetime[ms]        ┆ f64      ┆ u32    ┆ str     │
+ ╞═════════════════════╪══════════╪════════╪═════════╡
+ │ 2024-11-26 20:05:00 ┆ 91921.37 ┆ 65086  ┆ BTCUSDT │
+ │ 2024-11-26 20:10:00 ┆ 91595.99 ┆ null   ┆ BTCUSDT │
+ └─────────────────────┴──────────┴────────┴─────────┘
+ "#);
+	assert_eq!(Kline::from_df(&df).unwrap(), klines);
+
+	let df = df.drop("close").unwrap();
+	insta::assert_snapshot!(Kline::from_df(&df).unwrap_err(), @r#"not found: "close" not found"#);
+}
@@ -0,0 +1,148 @@
+//! `ExtendPolicy` decides what happens to a config missing a field that has a default — without
+//! ever reaching the interactive prompt here, so these run fine headless.
+
+use std::collections::HashMap;
+
+use v_utils::macros::{ExtendPolicy, FileFormat, SettingsSources};
+use v_utils_macros::Settings;
+
+#[derive(Clone, Debug, Default, Settings, v_utils_macros::MyConfigPrimitives)]
+struct AppConfig {
+	host: String,
+	port: u16,
+	debug: bool,
+}
+
+/// Own module, since `Settings` emits fixed-name items (`SettingsFlags`, …) into its scope.
+mod in_memory_by_default {
+	use v_utils_macros::Settings;
+
+	#[derive(Clone, Debug, Default, Settings, v_utils_macros::MyConfigPrimitives)]
+	#[settings(extend_policy = "extend-in-memory-only")]
+	pub struct AttrConfig {
+		pub host: String,
+		pub port: u16,
+	}
+
+	/// `SettingsFlags`' `config` is private to this module.
+	pub fn flags(config: std::path::PathBuf) -> SettingsFlags {
+		SettingsFlags {
+			config: Some(v_utils::io::ExpandedPath(config)),
+			..Default::default()
+		}
+	}
+}
+
+/// A config field of the same name as the policy, which its env var must not land in.
+mod field_named_extend_policy {
+	use v_utils_macros::Settings;
+
+	#[derive(Clone, Debug, Default, Settings, v_utils_macros::MyConfigPrimitives)]
+	pub struct PolicyFieldConfig {
+		pub host: String,
+		pub extend_policy: String,
+	}
+}
+
+fn host_only() -> Option<(FileFormat, String)> {
+	Some((FileFormat::Toml, "host = \"example.com\"\n".to_owned()))
+}
+
+fn policy_env(policy: &str) -> HashMap<String, String> {
+	HashMap::from([("V_UTILS_MACROS_EXTEND_POLICY".to_owned(), policy.to_owned())])
+}
+
+#[test]
+fn in_memory_fills_every_missing_field() {
+	let sources = SettingsSources {
+		file: host_only(),
+		env: policy_env("extend-in-memory-only"),
+		..Default::default()
+	};
+	let cfg = AppConfig::try_build_from(sources).unwrap();
+	assert_eq!(cfg.host, "example.com");
+	assert_eq!(cfg.port, 0);
+	assert!(!cfg.debug);
+}
+
+#[test]
+fn never_extend_errors() {
+	let sources = SettingsSources {
+		file: host_only(),
+		env: policy_env("never-extend"),
+		..Default::default()
+	};
+	assert!(AppConfig::try_build_from(sources).is_err());
+}
+
+#[test]
+fn policy_from_env() {
+	let sources = SettingsSources {
+		file: host_only(),
+		env: policy_env("extend-in-memory-only"),
+		..Default::default()
+	};
+	assert_eq!(AppConfig::try_build_from(sources).unwrap().port, 0);
+
+	let sources = SettingsSources {
+		file: host_only(),
+		env: policy_env("sometimes"),
+		..Default::default()
+	};
+	let err = AppConfig::try_build_from(sources).expect_err("an unknown policy must not be ignored");
+	assert!(err.to_string().contains("V_UTILS_MACROS_EXTEND_POLICY=sometimes"), "got: {err}");
+}
+
+#[test]
+fn policy_from_struct_attribute_yields_to_env_and_flag() {
+	use in_memory_by_default::{AttrConfig, flags};
+
+	let sources = SettingsSources {
+		file: host_only(),
+		..Default::default()
+	};
+	assert_eq!(AttrConfig::try_build_from(sources).unwrap().port, 0);
+
+	let sources = SettingsSources {
+		file: host_only(),
+		env: policy_env("never-extend"),
+		..Default::default()
+	};
+	assert!(AttrConfig::try_build_from(sources).is_err());
+
+	let tmp = tempfile::tempdir().unwrap();
+	let config_path = tmp.path().join("app.toml");
+	std::fs::write(&config_path, "host = \"example.com\"\n").unwrap();
+	assert!(AttrConfig::try_build_with_policy(flags(config_path.clone()), Some(ExtendPolicy::NeverExtend)).is_err());
+	assert_eq!(AttrConfig::try_build_with_policy(flags(config_path), None).unwrap().port, 0);
+}
+
+#[test]
+fn policy_env_var_is_not_a_config_field() {
+	use field_named_extend_policy::PolicyFieldConfig;
+
+	let sources = SettingsSources {
+		file: Some((FileFormat::Toml, "host = \"example.com\"\nextend_policy = \"from the file\"\n".to_owned())),
+		env: policy_env("never-extend"),
+		..Default::default()
+	};
+	assert_eq!(PolicyFieldConfig::try_build_from(sources).unwrap().extend_policy, "from the file");
+}
+
+#[test]
+fn always_extend_writes_the_file() {
+	let tmp = tempfile::tempdir().unwrap();
+	let config_path = tmp.path().join("app.toml");
+	std::fs::write(&config_path, "host = \"example.com\"\n").unwrap();
+
+	let flags = SettingsFlags {
+		config: Some(v_utils::io::ExpandedPath(config_path.clone())),
+		..Default::default()
+	};
+	let cfg = AppConfig::try_build_with_policy(flags, Some(ExtendPolicy::AlwaysExtend)).unwrap();
+	assert_eq!(cfg.port, 0);
+
+	let content = std::fs::read_to_string(&config_path).unwrap();
+	assert!(content.contains("port = 0"), "got:\n{content}");
+	assert!(content.contains("debug = false"), "got:\n{content}");
+}
@@ -0,0 +1,77 @@
+//! `try_build_from` resolves a config purely from in-memory sources — no XDG search, no process
+//! env, no prompt. Each test here builds its own `SettingsSources`, so unlike the file/env-driven
+//! Settings tests they are free to run in parallel.
+
+use std::collections::HashMap;
+
+use v_utils::macros::{FileFormat, SettingsSources};
+use v_utils_macros::Settings;
+
+#[derive(Clone, Debug, Default, Settings, v_utils_macros::MyConfigPrimitives)]
+struct AppConfig {
+	#[serde(default)]
+	host: String,
+	#[serde(default)]
+	port: u16,
+	#[serde(default)]
+	debug: bool,
+}
+
+/// Own module, since `Settings` emits fixed-name items (`SettingsFlags`, …) into its scope.
+mod strict {
+	use v_utils_macros::Settings;
+
+	/// `Default + Serialize`, so `try_build` would offer to extend a config missing `port`.
+	#[derive(Clone, Debug, Default, Settings, v_utils_macros::MyConfigPrimitives)]
+	pub struct StrictConfig {
+		pub host: String,
+		pub port: u16,
+	}
+}
+
+#[test]
+fn file_env_and_flags_layer_in_precedence_order() {
+	let sources = SettingsSources {
+		file: Some((FileFormat::Toml, "host = \"from-file\"\nport = 1\n".to_owned())),
+		env: HashMap::from([("V_UTILS_MACROS__PORT".to_owned(), "2".to_owned()), ("V_UTILS_MACROS__DEBUG".to_owned(), "true".to_owned())]),
+		flags: SettingsFlags {
+			host: Some("from-flags".to_owned()),
+			..Default::default()
+		},
+	};
+
+	let cfg = AppConfig::try_build_from(sources).unwrap();
+	// flags > file > env
+	assert_eq!(cfg.host, "from-flags");
+	assert_eq!(cfg.port, 1);
+	assert!(cfg.debug);
+}
+
+#[test]
+fn no_file_builds_from_defaults() {
+	let cfg = AppConfig::try_build_from(SettingsSources::default()).unwrap();
+	assert_eq!(cfg.host, "");
+	assert_eq!(cfg.port, 0);
+	assert!(!cfg.debug);
+}
+
+#[test]
+fn placeholders_are_rejected() {
+	let sources = SettingsSources {
+		file: Some((FileFormat::Json, r#"{ "host": "REQUIRED" }"#.to_owned())),
+		..Default::default()
+	};
+	let err = AppConfig::try_build_from(sources).expect_err("a placeholder must not load");
+	assert!(err.to_string().contains("  - host"), "got: {err}");
+}
+
+#[test]
+fn missing_field_errors_without_prompting() {
+	let sources = SettingsSources {
+		file: Some((FileFormat::Toml, "host = \"example.com\"\n".to_owned())),
+		..Default::default()
+	};
+	// With stdin attached to the test runner, a prompt here would hang rather than fail.
+	let err = strict::StrictConfig::try_build_from(sources).expect_err("a missing required field must error");
+	assert!(err.to_string().contains("port"), "got: {err}");
+}
@@ -0,0 +1,80 @@
+use clap::{Parser, ValueEnum};
+use insta::assert_snapshot;
+use v_utils_macros::StrEnum;
+
+#[derive(Clone, Copy, Debug, PartialEq, StrEnum)]
+#[str_enum(value_enum, schema)]
+pub enum Side {
+	Buy,
+	#[str_enum(alias = ["short", "s"])]
+	Sell,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, StrEnum)]
+#[str_enum(case = "snake")]
+pub enum TimeInForce {
+	GoodTillCancel,
+	#[str_enum(rename = "IOC", alias = "immediate")]
+	ImmediateOrCancel,
+	FillOrKill,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, StrEnum)]
+#[str_enum(case = "screaming-kebab")]
+pub enum Venue {
+	BinanceFutures,
+	Bybit,
+}
+
+#[derive(Debug, Parser)]
+struct Cli {
+	#[arg(long, ignore_case = true)]
+	side: Side,
+}
+
+#[test]
+fn names_and_aliases() {
+	assert_eq!(Side::ALL, &[Side::Buy, Side::Sell]);
+	assert_eq!(Side::Sell.to_string(), "sell");
+	assert_eq!(format!("{:>5}", Side::Buy), "  buy");
+	assert_eq!("SELL".parse::<Side>().unwrap(), Side::Sell);
+	assert_eq!("Short".parse::<Side>().unwrap(), Side::Sell);
+	assert_eq!("s".parse::<Side>().unwrap(), Side::Sell);
+
+	assert_eq!(TimeInForce::GoodTillCancel.as_str(), "good_till_cancel");
+	assert_eq!(TimeInForce::ImmediateOrCancel.to_string(), "IOC");
+	assert_eq!("ioc".parse::<TimeInForce>().unwrap(), TimeInForce::ImmediateOrCancel);
+	assert_eq!("IMMEDIATE".parse::<TimeInForce>().unwrap(), TimeInForce::ImmediateOrCancel);
+	assert_eq!("FILL_OR_KILL".parse::<TimeInForce>().unwrap(), TimeInForce::FillOrKill);
+
+	assert_eq!(Venue::BinanceFutures.to_string(), "BINANCE-FUTURES");
+	assert_eq!("binance-futures".parse::<Venue>().unwrap(), Venue::BinanceFutures);
+
+	let err = "fok".parse::<TimeInForce>().unwrap_err();
+	assert_snapshot!(err, @"invalid TimeInForce `fok`, expected one of: good_till_cancel, IOC, fill_or_kill");
+}
+
+#[test]
+fn serde_clap_and_schema() {
+	#[derive(Debug, serde::Deserialize, serde::Serialize)]
+	struct Order {
+		side: Side,
+		tif: TimeInForce,
+	}
+	let order: Order = serde_json::from_str(r#"{"side":"Short","tif":"immediate"}"#).unwrap();
+	assert_eq!((order.side, order.tif), (Side::Sell, TimeInForce::ImmediateOrCancel));
+	assert_snapshot!(serde_json::to_string(&order).unwrap(), @r#"{"side":"sell","tif":"IOC"}"#);
+	assert!(serde_json::from_str::<Order>(r#"{"side":"hold","tif":"IOC"}"#).is_err());
+
+	assert_eq!(Cli::try_parse_from(["app", "--side", "S"]).unwrap().side, Side::Sell);
+	// clap's own matching, so `ignore_case` is up to the arg
+	assert!(<Side as ValueEnum>::from_str("S", false).is_err());
+	assert_eq!(<Side as ValueEnum>::from_str("S", true).unwrap(), Side::Sell);
+	let possible = Side::value_variants().iter().map(|v| v.to_possible_value().unwrap().get_name().to_owned()).collect::<Vec<_>>();
+	assert_eq!(possible, ["buy", "sell"]);
+	assert!(Cli::try_parse_from(["app", "--side", "hold"]).is_err());
+
+	let schema = serde_json::to_value(schemars::schema_for!(Side)).unwrap();
+	assert_eq!(schema["type"], "string");
+	assert_eq!(schema["enum"], serde_json::json!(["buy", "sell", "short", "s"]));
+}
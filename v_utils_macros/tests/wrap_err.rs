@@ -86,3 +86,136 @@ fn test_own_question_mark() {
 	//assert_eq!(result.unwrap_err().to_string(), "inner: from inner");
 	insta::assert_snapshot!(result.unwrap_err().to_string(), @"inner: from inner");
 }
+
+#[wrap_err(diagnostic)]
+#[derive(Debug, thiserror::Error)]
+#[sysexit(NoInput)]
+#[help("create {path} first")]
+#[error("missing config at {path}")]
+pub struct MissingConfig {
+	path: String,
+}
+
+#[wrap_err(diagnostic)]
+#[derive(Debug, thiserror::Error)]
+pub enum AppError {
+	#[leaf]
+	#[sysexit(DataErr)]
+	#[help("expected a number, got `{val}`")]
+	#[code(my_app::bad_value)]
+	#[error("bad value: {val}")]
+	BadValue { val: String, attempts: u32 },
+
+	#[foreign]
+	#[sysexit(IoErr)]
+	Io(std::io::Error),
+
+	#[own]
+	#[sysexit(Config)]
+	Config(MissingConfig),
+
+	#[leaf]
+	#[error("no mapping")]
+	Unmapped,
+}
+
+#[test]
+fn sysexit_help_and_code() {
+	use miette::Diagnostic as _;
+	use v_utils::utils::SysexitCode as _;
+
+	let bad = AppError::new_bad_value("x".into(), 3);
+	assert_eq!(i32::from(bad.sysexit()), 65);
+	assert_eq!(bad.help().unwrap().to_string(), "expected a number, got `x`");
+	assert_eq!(bad.code().unwrap().to_string(), "my_app::bad_value");
+
+	let io: AppError = std::io::Error::other("disk full").into();
+	assert_eq!(i32::from(io.sysexit()), 74);
+	assert!(io.help().is_none());
+
+	// `#[own]` takes its own `#[sysexit]`, and the diagnostics of its source
+	let config: AppError = MissingConfig::new("~/.config/app.toml".into()).into();
+	assert_eq!(i32::from(config.sysexit()), 78);
+	assert_eq!(config.help().unwrap().to_string(), "create ~/.config/app.toml first");
+	assert!(config.code().is_none());
+
+	assert_eq!(i32::from(AppError::new_unmapped().sysexit()), 1);
+	assert_eq!(i32::from(MissingConfig::new("x".into()).sysexit()), 66);
+}
+
+#[wrap_err]
+#[derive(Debug, thiserror::Error)]
+pub enum StartupError {
+	#[own]
+	App(AppError),
+
+	#[leaf]
+	#[sysexit(Usage)]
+	#[error("unknown flag")]
+	UnknownFlag,
+}
+
+#[test]
+fn own_without_sysexit_takes_its_sources() {
+	use v_utils::utils::SysexitCode as _;
+
+	let app: StartupError = AppError::new_bad_value("x".into(), 3).into();
+	assert_eq!(i32::from(app.sysexit()), 65);
+	let unmapped: StartupError = AppError::new_unmapped().into();
+	assert_eq!(i32::from(unmapped.sysexit()), 1);
+	assert_eq!(i32::from(StartupError::new_unknown_flag().sysexit()), 64);
+}
+
+#[wrap_err]
+#[derive(Debug, thiserror::Error)]
+#[error("tuple error: {0}")]
+pub struct TupleError(String);
+
+#[wrap_err]
+#[derive(Debug, thiserror::Error)]
+#[error("unit error")]
+pub struct UnitError;
+
+#[wrap_err]
+#[derive(Debug, thiserror::Error)]
+pub enum ReadError<K: std::fmt::Debug + std::fmt::Display> {
+	#[foreign]
+	#[error("reading {path:?}: {source}")]
+	Io { source: std::io::Error, path: std::path::PathBuf },
+
+	#[foreign]
+	#[error("parsing {key} at line {line}: {source}")]
+	Parse { source: std::num::ParseIntError, key: K, line: usize },
+
+	#[leaf]
+	#[error("missing {key}")]
+	Missing { key: K },
+}
+
+#[test]
+fn tuple_unit_and_generic() {
+	let e = TupleError::new("oops".into());
+	assert_eq!(e.to_string(), "tuple error: oops");
+	assert!(std::error::request_ref::<std::backtrace::Backtrace>(&e).is_some());
+
+	assert_eq!(UnitError::new().to_string(), "unit error");
+
+	let e: ReadError<&str> = ReadError::new_missing("port");
+	assert_eq!(e.to_string(), "missing port");
+}
+
+#[test]
+fn foreign_with_context() {
+	let read = || -> Result<String, ReadError<String>> {
+		let path = std::path::Path::new("/nonexistent/v_utils/config.toml");
+		let s = std::fs::read_to_string(path).with_path(path)?;
+		Ok(s)
+	};
+	let e = read().unwrap_err();
+	assert!(e.to_string().starts_with("reading \"/nonexistent/v_utils/config.toml\": "), "{e}");
+	assert!(matches!(&e, ReadError::Io { source, .. } if source.kind() == std::io::ErrorKind::NotFound));
+
+	let e = "x".parse::<u16>().with_key_line("port", 3usize).unwrap_err();
+	let e: ReadError<String> = e;
+	assert_eq!(e.to_string(), "parsing port at line 3: invalid digit found in string");
+}